@@ -0,0 +1,396 @@
+//! Assembunny, the assembly language of the Easter Bunny's computers.
+//! 
+//! Days 12, 23 and 25 run assembunny programs on a machine with four
+//! registers `a`, `b`, `c` and `d`, all starting at 0:
+//! 
+//! ```text
+//! cpy x y   copies x (a value or a register) into register y
+//! inc x     increases register x by one
+//! dec x     decreases register x by one
+//! jnz x y   jumps y instructions away if x is not zero
+//! tgl x     toggles the instruction x away (day 23)
+//! out x     transmits x as the next value of the clock signal (day 25)
+//! ```
+//! 
+//! `tgl` turns `inc` into `dec` and every other one-argument instruction
+//! into `inc`, `jnz` into `cpy` and every other two-argument instruction
+//! into `jnz`. Instructions made invalid that way, like `inc 1`, are
+//! skipped. A program halts when it jumps outside of itself.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A register (0 to 3 for `a` to `d`) or an immediate value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Reg(usize),
+    Value(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    Cpy(Operand, Operand),
+    Inc(Operand),
+    Dec(Operand),
+    Jnz(Operand, Operand),
+    Tgl(Operand),
+    Out(Operand),
+}
+
+/// Why a line of a program couldn't be parsed, with its line number
+/// counted from 1.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnknownInstruction(usize, String),
+    WrongOperandCount(usize, String),
+    InvalidOperand(usize, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnknownInstruction(line, txt) => write!(f, "line {}: unknown instruction {:?}", line, txt),
+            ParseError::WrongOperandCount(line, txt) => write!(f, "line {}: wrong number of operands in {:?}", line, txt),
+            ParseError::InvalidOperand(line, txt) => write!(f, "line {}: invalid operand {:?}", line, txt),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Operand {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "a" => Ok(Operand::Reg(0)),
+            "b" => Ok(Operand::Reg(1)),
+            "c" => Ok(Operand::Reg(2)),
+            "d" => Ok(Operand::Reg(3)),
+            _ => s.parse::<i64>().map(Operand::Value).map_err(|_| ()),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Reg(r) => write!(f, "{}", (b'a' + *r as u8) as char),
+            Operand::Value(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Cpy(x, y) => write!(f, "cpy {} {}", x, y),
+            Instruction::Inc(x) => write!(f, "inc {}", x),
+            Instruction::Dec(x) => write!(f, "dec {}", x),
+            Instruction::Jnz(x, y) => write!(f, "jnz {} {}", x, y),
+            Instruction::Tgl(x) => write!(f, "tgl {}", x),
+            Instruction::Out(x) => write!(f, "out {}", x),
+        }
+    }
+}
+
+impl Instruction {
+    /// Parses a single line, `line` is only used for error messages.
+    pub fn parse(txt: &str, line: usize) -> Result<Instruction, ParseError> {
+        let mut parts = txt.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let mut operands: Vec<Operand> = Vec::new();
+        for part in parts {
+            operands.push(part.parse().map_err(|_| ParseError::InvalidOperand(line, part.to_string()))?);
+        }
+        let wrong_count = || ParseError::WrongOperandCount(line, txt.to_string());
+        match (name, operands.as_slice()) {
+            ("cpy", &[x, y]) => Ok(Instruction::Cpy(x, y)),
+            ("inc", &[x]) => Ok(Instruction::Inc(x)),
+            ("dec", &[x]) => Ok(Instruction::Dec(x)),
+            ("jnz", &[x, y]) => Ok(Instruction::Jnz(x, y)),
+            ("tgl", &[x]) => Ok(Instruction::Tgl(x)),
+            ("out", &[x]) => Ok(Instruction::Out(x)),
+            ("cpy" | "inc" | "dec" | "jnz" | "tgl" | "out", _) => Err(wrong_count()),
+            _ => Err(ParseError::UnknownInstruction(line, name.to_string())),
+        }
+    }
+
+    /// The instruction after `tgl` hit it.
+    pub fn toggle(self) -> Instruction {
+        match self {
+            Instruction::Inc(x) => Instruction::Dec(x),
+            Instruction::Dec(x) | Instruction::Tgl(x) | Instruction::Out(x) => Instruction::Inc(x),
+            Instruction::Jnz(x, y) => Instruction::Cpy(x, y),
+            Instruction::Cpy(x, y) => Instruction::Jnz(x, y),
+        }
+    }
+}
+
+/// Parses a whole program, empty lines are ignored.
+pub fn parse_program<S: AsRef<str>>(lines: &[S]) -> Result<Vec<Instruction>, ParseError> {
+    lines.iter().enumerate()
+        .filter(|(_n, l)| !l.as_ref().trim().is_empty())
+        .map(|(n, l)| Instruction::parse(l.as_ref(), n + 1))
+        .collect()
+}
+
+/// A loop of several instructions executed in one go by the optimiser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fused {
+    /// `inc dst`, `dec counter`, `jnz counter -2` in either order of the
+    /// first two: `dst += counter; counter = 0`
+    Add { dst: usize, counter: usize },
+    /// `cpy src inner`, an add loop of `inner` into `dst`, `dec outer`,
+    /// `jnz outer -5`: `dst += src * outer; inner = 0; outer = 0`
+    Mul { dst: usize, src: Operand, inner: usize, outer: usize },
+}
+
+impl Fused {
+    /// The number of instructions replaced.
+    pub fn size(&self) -> i64 {
+        match self {
+            Fused::Add { .. } => 3,
+            Fused::Mul { .. } => 6,
+        }
+    }
+}
+
+impl fmt::Display for Fused {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Fused::Add { dst, counter } => {
+                let (dst, counter) = (Operand::Reg(dst), Operand::Reg(counter));
+                write!(f, "{} += {}, {} = 0", dst, counter, counter)
+            },
+            Fused::Mul { dst, src, inner, outer } => {
+                let (dst, inner, outer) = (Operand::Reg(dst), Operand::Reg(inner), Operand::Reg(outer));
+                write!(f, "{} += {} * {}, {} = 0, {} = 0", dst, src, outer, inner, outer)
+            },
+        }
+    }
+}
+
+fn match_add(code: &[Instruction]) -> Option<Fused> {
+    use Instruction::{Dec, Inc, Jnz};
+    use Operand::{Reg, Value};
+    match *code {
+        [Inc(Reg(dst)), Dec(Reg(counter)), Jnz(Reg(test), Value(-2)), ..]
+        | [Dec(Reg(counter)), Inc(Reg(dst)), Jnz(Reg(test), Value(-2)), ..]
+            if dst != counter && counter == test => Some(Fused::Add { dst, counter }),
+        _ => None,
+    }
+}
+
+fn match_mul(code: &[Instruction]) -> Option<Fused> {
+    use Instruction::{Cpy, Dec, Jnz};
+    use Operand::{Reg, Value};
+    let (src, inner, outer) = match *code {
+        [Cpy(src, Reg(inner)), _, _, _, Dec(Reg(outer)), Jnz(Reg(test), Value(-5)), ..]
+            if outer == test => (src, inner, outer),
+        _ => return None,
+    };
+    match match_add(&code[1..]) {
+        Some(Fused::Add { dst, counter }) if counter == inner && outer != dst && outer != inner
+            && ![Reg(dst), Reg(inner), Reg(outer)].contains(&src) => Some(Fused::Mul { dst, src, inner, outer }),
+        _ => None,
+    }
+}
+
+/// Finds the loops starting at each instruction that can be fused.
+///
+/// Jumps into the middle of a loop still work because only its first
+/// instruction is replaced, the others are kept as they are.
+pub fn optimise(program: &[Instruction]) -> Vec<Option<Fused>> {
+    (0..program.len())
+        .map(|i| match_mul(&program[i..]).or_else(|| match_add(&program[i..])))
+        .collect()
+}
+
+/// What a single step of the machine did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// an instruction was executed
+    Continue,
+    /// an `out` instruction sent this value
+    Output(i64),
+    /// the program counter is outside of the program
+    Halted,
+}
+
+/// The machine running an assembunny program.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Vm {
+    pub registers: [i64; 4],
+    pub pc: i64,
+    pub program: Vec<Instruction>,
+    /// the fused loops by starting instruction, empty if not optimised
+    fused: Vec<Option<Fused>>,
+}
+
+impl Vm {
+    pub fn new(program: Vec<Instruction>) -> Vm {
+        Vm { registers: [0; 4], pc: 0, program, fused: Vec::new() }
+    }
+
+    /// A machine running add and multiply loops as single steps.
+    pub fn optimised(program: Vec<Instruction>) -> Vm {
+        let fused = optimise(&program);
+        Vm { registers: [0; 4], pc: 0, program, fused }
+    }
+
+    pub fn is_optimised(&self) -> bool {
+        !self.fused.is_empty()
+    }
+
+    /// The program with the fused loops as comments.
+    pub fn listing(&self) -> String {
+        let mut txt = String::new();
+        for (i, instr) in self.program.iter().enumerate() {
+            let line = format!("{:>3}  {}", i, instr);
+            match self.fused.get(i) {
+                Some(Some(fused)) => txt.push_str(&format!("{:<16}; {}\n", line, fused)),
+                _ => txt.push_str(&format!("{}\n", line)),
+            }
+        }
+        txt
+    }
+
+    /// Executes a fused loop, unless its counters would make the original
+    /// loop run differently or the result does not fit.
+    fn execute_fused(&mut self, fused: Fused) -> bool {
+        match fused {
+            Fused::Add { dst, counter } => {
+                if self.registers[counter] <= 0 {
+                    return false;
+                }
+                let Some(sum) = self.registers[dst].checked_add(self.registers[counter]) else {
+                    return false;
+                };
+                self.registers[dst] = sum;
+                self.registers[counter] = 0;
+            },
+            Fused::Mul { dst, src, inner, outer } => {
+                let factor = self.value(src);
+                if factor <= 0 || self.registers[outer] <= 0 {
+                    return false;
+                }
+                let Some(sum) = factor.checked_mul(self.registers[outer])
+                    .and_then(|product| self.registers[dst].checked_add(product)) else {
+                    return false;
+                };
+                self.registers[dst] = sum;
+                self.registers[inner] = 0;
+                self.registers[outer] = 0;
+            },
+        }
+        self.pc += fused.size();
+        true
+    }
+
+    fn value(&self, x: Operand) -> i64 {
+        match x {
+            Operand::Reg(r) => self.registers[r],
+            Operand::Value(v) => v,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc < 0 || self.pc >= self.program.len() as i64
+    }
+
+    /// Executes the instruction at the program counter.
+    pub fn step(&mut self) -> Step {
+        if self.is_halted() {
+            return Step::Halted;
+        }
+        if let Some(&Some(fused)) = self.fused.get(self.pc as usize) {
+            if self.execute_fused(fused) {
+                return Step::Continue;
+            }
+        }
+        let mut result = Step::Continue;
+        let mut next = self.pc + 1;
+        match self.program[self.pc as usize] {
+            Instruction::Cpy(x, Operand::Reg(r)) => self.registers[r] = self.value(x),
+            Instruction::Inc(Operand::Reg(r)) => self.registers[r] += 1,
+            Instruction::Dec(Operand::Reg(r)) => self.registers[r] -= 1,
+            Instruction::Jnz(x, y) => if self.value(x) != 0 {
+                next = self.pc + self.value(y);
+            },
+            Instruction::Tgl(x) => {
+                let target = self.pc + self.value(x);
+                if target >= 0 && target < self.program.len() as i64 {
+                    self.program[target as usize] = self.program[target as usize].toggle();
+                    if self.is_optimised() {
+                        self.fused = optimise(&self.program);
+                    }
+                }
+            },
+            Instruction::Out(x) => result = Step::Output(self.value(x)),
+            // invalid after a toggle, skipped
+            Instruction::Cpy(_, Operand::Value(_)) | Instruction::Inc(Operand::Value(_))
+            | Instruction::Dec(Operand::Value(_)) => (),
+        }
+        self.pc = next;
+        result
+    }
+
+    /// Runs until the program halts, output is dropped.
+    pub fn run(&mut self) {
+        while self.step() != Step::Halted {}
+    }
+
+    /// Runs until the next `out` instruction and returns its value, or
+    /// `None` if the program halts first.
+    pub fn run_until_output(&mut self) -> Option<i64> {
+        loop {
+            match self.step() {
+                Step::Continue => (),
+                Step::Output(v) => return Some(v),
+                Step::Halted => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the program both ways from `a` and returns register a.
+    fn run_both(lines: &[&str], a: i64) -> i64 {
+        let program = parse_program(lines).unwrap();
+        let mut naive = Vm::new(program.clone());
+        let mut fast = Vm::optimised(program);
+        naive.registers[0] = a;
+        fast.registers[0] = a;
+        naive.run();
+        fast.run();
+        assert_eq!(fast.registers, naive.registers);
+        assert_eq!(fast.program, naive.program);
+        fast.registers[0]
+    }
+
+    #[test]
+    fn fused_after_toggles() {
+        // the day 23 kind of program: a! + 79 * 73, toggling its own tail
+        let lines = [
+            "cpy a b", "dec b", "cpy a d", "cpy 0 a", "cpy b c", "inc a", "dec c", "jnz c -2", "dec d",
+            "jnz d -5", "dec b", "cpy b c", "cpy c d", "dec d", "inc c", "jnz d -2", "tgl c", "cpy -16 c",
+            "jnz 1 c", "cpy 79 c", "jnz 73 d", "inc a", "inc d", "jnz d -2", "inc c", "jnz c -5",
+        ];
+        assert_eq!(run_both(&lines, 7), 5040 + 79 * 73);
+    }
+
+    #[test]
+    fn fused_overflow_falls_back() {
+        let mut vm = Vm::optimised(parse_program(&["inc a", "dec b", "jnz b -2"]).unwrap());
+        assert!(vm.is_optimised());
+        vm.registers = [i64::MAX - 1, 3, 0, 0];
+        vm.step();
+        // only the `inc a` ran, the fused add would not fit
+        assert_eq!(vm.registers, [i64::MAX, 3, 0, 0]);
+        assert_eq!(vm.pc, 1);
+    }
+}
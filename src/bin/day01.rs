@@ -56,6 +56,8 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+use aoc2016::grid::{Direction, Point};
+
 fn read_data<P>(name: P) -> Vec<(String,u32)>
 
 where P: AsRef<Path> {
@@ -64,66 +66,23 @@ where P: AsRef<Path> {
     s.split(',').map(|x|x.trim().split_at(1)).map(|(o,d)| (o.to_owned(), d.parse::<u32>().unwrap())).collect()
 }
 
-fn movement(data: &Vec<(String, u32)>) {
-    let mut places: HashSet<(i32, i32)> = HashSet::new();
-    let mut curpos: (i32, i32) = (0,0);
-    let mut nextpos = curpos;
-    let mut xpos = curpos;
-    let mut direction: i32 = 0; // 0 = North, 1 = East, 2 = South, 3 = West
+fn movement(data: &[(String, u32)]) {
+    let mut places: HashSet<Point> = HashSet::new();
+    let mut curpos = Point::default();
+    let mut xpos: Option<Point> = None; // first place visited twice
+    let mut direction = Direction::North;
     places.insert(curpos);
     for (r, d) in data {
-        if r.eq("R") {
-            direction = (direction + 1) % 4;
-        } else { 
-            direction = direction - 1;
-            if direction < 0 {
-                direction = 3;
-            }
-        }
-        match direction {
-            0 => { nextpos.1 += *d as i32; }
-            1 => { nextpos.0 += *d as i32; }
-            2 => { nextpos.1 -= *d as i32; }
-            3 => { nextpos.0 -= *d as i32; }
-            _ => { println!("Error in direction"); }
-        }
-        {
-            let a: i32;
-            let b: i32;
-            if curpos.0 == nextpos.0 {
-                if curpos.1 > nextpos.1 {
-                    a = nextpos.1;
-                    b = curpos.1;
-                } else {
-                    a = curpos.1 + 1;  // cur_pos ist erfasst
-                    b = nextpos.1 + 1; // wg. Schleifenobergrenze
-                }
-                for y in a..b {
-                    if ! places.insert((curpos.0, y)) && xpos == (0, 0) {
-                        xpos = (curpos.0, y);
-                    }
-                }
-            } else if curpos.1 == curpos.1 {
-                if curpos.0 > nextpos.0 {
-                    a = nextpos.0;
-                    b = curpos.0;
-                } else {
-                    a = curpos.0 + 1;  // cur_pos ist erfasst
-                    b = nextpos.0 + 1; // wg. Schleifenobergrenze
-                }
-                for x in a..b {
-                    if ! places.insert((x, curpos.1)) && xpos == (0, 0) {
-                        xpos = (x, curpos.1);
-                    }
-                }
-            } else {
-                println!("Logikfehler! {:?} {:?}", curpos, nextpos);
+        direction = if r.eq("R") { direction.turn_right() } else { direction.turn_left() };
+        for _ in 0..*d {
+            curpos = curpos + direction.delta();
+            if !places.insert(curpos) && xpos.is_none() {
+                xpos = Some(curpos);
             }
         }
-        curpos = nextpos;
     }
-    println!("Part 1: {}", curpos.0.abs() + curpos.1.abs());
-    println!("Part 2: {}", xpos.0.abs() + xpos.1.abs());
+    println!("Part 1: {}", curpos.manhattan());
+    println!("Part 2: {}", xpos.map_or(0, |p| p.manhattan()));
 }
 
 fn main() {
@@ -65,7 +65,7 @@
 //! 
 //! Answer: 424a0197
 
-use md5::{Md5, Digest, digest::FixedOutput};
+use aoc2016::hashing::Seeded;
 
 fn main () {
     let mut i: u32 = 0;
@@ -73,11 +73,9 @@ fn main () {
     let mut rc_2: u32 = 0;
     let mut code_1 = String::new();
     let mut code_2 = ['_'; 8];
+    let door = Seeded::new(b"abbhdwsy");
     while i < u32::MAX {
-        let mut hash = <Md5 as Digest>::new();
-        hash.update(format!("abbhdwsy{}", i));
-        let result = hash.finalize_fixed();
-        let a = result.as_slice();
+        let a = door.hash(i.to_string().as_bytes());
         if a[0..2] == [0;2] && a[2] < 16 {
             if rc_1 < 8 {
                 // Solution Part 1
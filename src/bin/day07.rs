@@ -53,9 +53,14 @@
 //! Answer: 242
 
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 fn read_data<P>(name: P) -> Vec<String>
 where P: AsRef<Path> {
@@ -63,95 +68,431 @@ where P: AsRef<Path> {
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn check_pattern_abba(txt: &[u8]) -> bool {
-    for i in 1..=txt.len()-3 {
-        if txt[i-1] == txt[i+2]
-           && txt[i] == txt[i+1]
-           && txt[i-1] != txt[i] {
-            return true;
+/// Reasons why a line is not a valid IPv7 address. The position is the
+/// byte offset of the offending character.
+#[derive(Debug, PartialEq)]
+enum Ipv7Error {
+    NestedBracket(usize),
+    UnopenedBracket(usize),
+    UnclosedBracket(usize),
+    EmptyHypernet(usize),
+    InvalidChar(usize, char),
+}
+
+impl fmt::Display for Ipv7Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ipv7Error::NestedBracket(pos) => write!(f, "nested '[' at {}", pos),
+            Ipv7Error::UnopenedBracket(pos) => write!(f, "']' without '[' at {}", pos),
+            Ipv7Error::UnclosedBracket(pos) => write!(f, "'[' at {} is never closed", pos),
+            Ipv7Error::EmptyHypernet(pos) => write!(f, "empty hypernet sequence at {}", pos),
+            Ipv7Error::InvalidChar(pos, ch) => write!(f, "invalid character {:?} at {}", ch, pos),
         }
     }
-    false
 }
 
-fn find_pattern_aba(txt: &[u8]) -> Vec<[u8;3]> {
-    let mut result: Vec<[u8;3]> = Vec::new();
-    for i in 0..=txt.len()-3 {
-        if txt[i] == txt[i+2] && txt[i] != txt[i+1] {
-            let a: [u8;3] = [txt[i],txt[i+1],txt[i+2]];
-            result.push(a);
+/// An IPv7 address split into its supernet sequences (outside square
+/// brackets) and hypernet sequences (inside square brackets).
+#[derive(Debug, Default, PartialEq)]
+struct Ipv7Addr {
+    supernets: Vec<String>,
+    hypernets: Vec<String>,
+}
+
+impl FromStr for Ipv7Addr {
+    type Err = Ipv7Error;
+
+    fn from_str(addr: &str) -> Result<Self, Self::Err> {
+        let mut result = Ipv7Addr::default();
+        let mut segment = String::new();
+        // position of the '[' of the hypernet we are in, if any
+        let mut open: Option<usize> = None;
+        for (pos, ch) in addr.char_indices() {
+            match ch {
+                '[' => {
+                    if open.is_some() {
+                        return Err(Ipv7Error::NestedBracket(pos));
+                    }
+                    if !segment.is_empty() {
+                        result.supernets.push(std::mem::take(&mut segment));
+                    }
+                    open = Some(pos);
+                },
+                ']' => {
+                    let Some(start) = open else {
+                        return Err(Ipv7Error::UnopenedBracket(pos));
+                    };
+                    if segment.is_empty() {
+                        return Err(Ipv7Error::EmptyHypernet(start));
+                    }
+                    result.hypernets.push(std::mem::take(&mut segment));
+                    open = None;
+                },
+                'a'..='z' => segment.push(ch),
+                _ => return Err(Ipv7Error::InvalidChar(pos, ch)),
+            }
+        }
+        if let Some(start) = open {
+            return Err(Ipv7Error::UnclosedBracket(start));
         }
+        if !segment.is_empty() {
+            result.supernets.push(segment);
+        }
+        Ok(result)
     }
-    result
 }
 
-fn find_pattern_bab(txt: &[u8]) -> Vec<[u8;3]> {
-    let mut result: Vec<[u8;3]> = Vec::new();
-    for i in 0..=txt.len()-3 {
-        if txt[i] == txt[i+2] && txt[i] != txt[i+1] {
-            // flip the letter to make bab look aba for book keeping
-            let a: [u8;3] = [txt[i+1],txt[i],txt[i+1]];
-            result.push(a);
+impl Ipv7Addr {
+    fn supernets(&self) -> impl Iterator<Item = &str> {
+        self.supernets.iter().map(|s| s.as_str())
+    }
+
+    fn hypernets(&self) -> impl Iterator<Item = &str> {
+        self.hypernets.iter().map(|s| s.as_str())
+    }
+
+    fn supports(&self, protocol: &Protocol) -> bool {
+        let len = protocol.len;
+        match protocol.rule {
+            Rule::SupernetOnly => {
+                self.supernets().any(|s| find_palindromes(s.as_bytes(), len).next().is_some())
+                    && !self.hypernets().any(|s| find_palindromes(s.as_bytes(), len).next().is_some())
+            },
+            Rule::Mirrored => {
+                let inside: HashSet<&[u8]> = self.hypernets()
+                    .flat_map(|s| find_palindromes(s.as_bytes(), len)).collect();
+                self.supernets()
+                    .flat_map(|s| find_palindromes(s.as_bytes(), len))
+                    .any(|p| inside.contains(mirror(p).as_slice()))
+            },
         }
     }
+
+    fn supports_tls(&self) -> bool {
+        self.supports(&TLS)
+    }
+
+    fn supports_ssl(&self) -> bool {
+        self.supports(&SSL)
+    }
+}
+
+/// How the palindromes found in supernet and hypernet sequences have to
+/// relate for an address to support a protocol.
+#[derive(Debug)]
+enum Rule {
+    /// at least one match in a supernet and none in any hypernet
+    SupernetOnly,
+    /// a supernet match whose mirror (see `mirror`) is found in a hypernet
+    Mirrored,
+}
+
+/// A protocol is described by the length of its palindromes and the rule
+/// connecting supernets and hypernets, e.g. an ABCBA variant of SSL would
+/// be `Protocol { len: 5, rule: Rule::Mirrored }`.
+#[derive(Debug)]
+struct Protocol {
+    len: usize,
+    rule: Rule,
+}
+
+/// ABBA outside, but not inside square brackets
+const TLS: Protocol = Protocol { len: 4, rule: Rule::SupernetOnly };
+/// ABA outside with a corresponding BAB inside square brackets
+const SSL: Protocol = Protocol { len: 3, rule: Rule::Mirrored };
+
+/// All windows of `len` characters that read the same in both directions
+/// and whose first half consists of distinct characters, so `abba`, `aba`
+/// and `abcba` match, but `aaaa`, `aaa` and `abaab` don't. Nothing shorter
+/// than two characters matches.
+fn find_palindromes(txt: &[u8], len: usize) -> impl Iterator<Item = &[u8]> {
+    let half = len.div_ceil(2);
+    txt.windows(len.max(1)).filter(move |w| {
+        len >= 2 && w.iter().eq(w.iter().rev())
+            && (1..half).all(|i| !w[..i].contains(&w[i]))
+    })
+}
+
+/// The same characters in reversed positions: the first half of the
+/// pattern is reversed and mirrored again, so `aba` becomes `bab` and
+/// `abcba` becomes `cbabc`.
+fn mirror(pattern: &[u8]) -> Vec<u8> {
+    let half = pattern.len().div_ceil(2);
+    let mut result: Vec<u8> = pattern[..half].iter().rev().copied().collect();
+    let tail: Vec<u8> = result[..pattern.len() / 2].iter().rev().copied().collect();
+    result.extend(tail);
     result
 }
 
-fn check_address_ssl(addr: &str) -> bool{
-    let addr_b = addr.as_bytes();
-    let mut ipas: HashSet<[u8;3]> = HashSet::new();
-    let mut nhss: HashSet<[u8;3]> = HashSet::new();
-    let mut lower: usize = 0;
-    let mut upper: usize = 0;
-    while upper < addr.len() {
-        if addr_b[upper] == b'[' {
-            ipas.extend(find_pattern_aba(&addr_b[lower..upper]));
-            lower = upper + 1;
-        } else if addr_b[upper] == b']' {
-            nhss.extend(find_pattern_bab(&addr_b[lower..upper]));
-            lower = upper + 1;
-        } else if addr.len() == upper + 1 {
-            ipas.extend(find_pattern_aba(&addr_b[lower..=upper]));
-        }
-        upper += 1;
-    }
-    ! ipas.is_disjoint(&nhss)
-}
-
-fn check_address_tls(addr: &str) -> bool {
-    let addr_b = addr.as_bytes();
-    let mut result: bool = false;
-    let mut lower: usize = 0;
-    let mut upper: usize = 0;
-    while upper < addr.len() {
-        if addr_b[upper] == b'[' {
-            result |= check_pattern_abba(&addr_b[lower..upper]);
-            lower = upper + 1;
-        } else if addr_b[upper] == b']' {
-            if check_pattern_abba(&addr_b[lower..upper]) {
-                return  false;
+/// Which protocol a generated address is guaranteed to support.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Force {
+    Nothing,
+    Tls,
+    Ssl,
+}
+
+fn random_segment(rng: &mut StdRng) -> Vec<u8> {
+    // a small alphabet makes ABBA and ABA sequences likely
+    (0..rng.gen_range(1..=12)).map(|_| rng.gen_range(b'a'..=b'e')).collect()
+}
+
+/// A random segment of letters that doesn't contain any ABBA.
+fn random_segment_without_abba(rng: &mut StdRng) -> Vec<u8> {
+    let mut result: Vec<u8> = Vec::new();
+    for _i in 0..rng.gen_range(1..=12) {
+        loop {
+            result.push(rng.gen_range(b'a'..=b'e'));
+            if result.len() < 4 || !check_pattern_abba(&result[result.len()-4..]) {
+                break;
             }
-            lower = upper + 1;
-        } else if addr.len() == upper + 1 {
-            result |= check_pattern_abba(&addr_b[lower..=upper]);
+            result.pop();
         }
-        upper += 1;
     }
     result
 }
 
-fn main() {
+fn check_pattern_abba(txt: &[u8]) -> bool {
+    find_palindromes(txt, TLS.len).next().is_some()
+}
+
+fn insert_random(rng: &mut StdRng, segment: &mut Vec<u8>, pattern: &[u8]) {
+    let pos = rng.gen_range(0..=segment.len());
+    segment.splice(pos..pos, pattern.iter().copied());
+}
+
+/// The fewest segments of an address supporting `force`: forcing SSL
+/// needs a hypernet for the BAB.
+fn min_segments(force: Force) -> usize {
+    if force == Force::Ssl { 2 } else { 1 }
+}
+
+/// Builds an address of `segments` alternating supernet and hypernet
+/// sequences, starting with a supernet.
+fn generate_address(rng: &mut StdRng, segments: usize, force: Force) -> String {
+    assert!(segments >= min_segments(force));
+    let mut parts: Vec<Vec<u8>> = (0..segments).map(|i| {
+        if i % 2 == 1 && force == Force::Tls {
+            random_segment_without_abba(rng)
+        } else {
+            random_segment(rng)
+        }
+    }).collect();
+    let supernet = 2 * rng.gen_range(0..segments.div_ceil(2));
+    let hypernet = 2 * rng.gen_range(0..(segments / 2).max(1)) + 1;
+    let a = rng.gen_range(b'a'..=b'z');
+    let b = loop {
+        let b = rng.gen_range(b'a'..=b'z');
+        if b != a {
+            break b;
+        }
+    };
+    match force {
+        Force::Nothing => (),
+        Force::Tls => insert_random(rng, &mut parts[supernet], &[a, b, b, a]),
+        Force::Ssl => {
+            insert_random(rng, &mut parts[supernet], &[a, b, a]);
+            insert_random(rng, &mut parts[hypernet], &[b, a, b]);
+        },
+    }
+    let mut result = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        let part = String::from_utf8_lossy(part);
+        if i % 2 == 0 {
+            result.push_str(&part);
+        } else {
+            result.push_str(&format!("[{}]", part));
+        }
+    }
+    result
+}
+
+fn solve() {
     let data = read_data("data/07/input.txt");
     let mut result_1: u32 = 0;
     let mut result_2: u32 = 0;
     for i in &data {
-        if check_address_tls(i) {
+        let addr = match i.parse::<Ipv7Addr>() {
+            Ok(addr) => addr,
+            Err(e) => {
+                println!("Skipping {}: {}", i, e);
+                continue;
+            }
+        };
+        if addr.supports_tls() {
             result_1 += 1;
         }
-        if check_address_ssl(i) {
+        if addr.supports_ssl() {
             result_2 += 1;
         }
     }
     println!("Part 1: {}", result_1);
     println!("Part 2: {}", result_2);
-}
\ No newline at end of file
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| {
+        let i = args.iter().position(|a| a == name)?;
+        match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) {
+            Some(value) => Some(value),
+            None => {
+                eprintln!("Error: {} needs a number", name);
+                std::process::exit(1);
+            }
+        }
+    };
+    let seed = option("--seed").unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    if let Some(segments) = option("--generate") {
+        let force = match (args.iter().any(|a| a == "--tls"), args.iter().any(|a| a == "--ssl")) {
+            (true, true) => {
+                eprintln!("Error: --tls and --ssl can't be forced together");
+                std::process::exit(1);
+            },
+            (true, false) => Force::Tls,
+            (false, true) => Force::Ssl,
+            (false, false) => Force::Nothing,
+        };
+        let segments = segments as usize;
+        if segments < min_segments(force) {
+            eprintln!("Error: {} segments are too few, at least {} needed", segments, min_segments(force));
+            std::process::exit(1);
+        }
+        for _i in 0..option("--count").unwrap_or(1) {
+            println!("{}", generate_address(&mut rng, segments, force));
+        }
+    } else {
+        solve();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("ab[cd[ef]]gh".parse::<Ipv7Addr>(), Err(Ipv7Error::NestedBracket(5)));
+        assert_eq!("abc]def".parse::<Ipv7Addr>(), Err(Ipv7Error::UnopenedBracket(3)));
+        assert_eq!("abc[def]gh[ij".parse::<Ipv7Addr>(), Err(Ipv7Error::UnclosedBracket(10)));
+        assert_eq!("abc[def][]gh".parse::<Ipv7Addr>(), Err(Ipv7Error::EmptyHypernet(8)));
+        assert_eq!("abc[dEf]".parse::<Ipv7Addr>(), Err(Ipv7Error::InvalidChar(5, 'E')));
+    }
+
+    #[test]
+    fn parse_segments() {
+        let addr = "[ab]cd[ef]gh".parse::<Ipv7Addr>().unwrap();
+        assert_eq!(addr.supernets, ["cd", "gh"]);
+        assert_eq!(addr.hypernets, ["ab", "ef"]);
+    }
+
+    #[test]
+    fn palindromes() {
+        let found: Vec<&[u8]> = find_palindromes(b"xabbaaaaxyxabcbaz", 4).collect();
+        assert_eq!(found, [b"abba"]);
+        let found: Vec<&[u8]> = find_palindromes(b"aaabcbabcba", 5).collect();
+        assert_eq!(found, [b"abcba", b"cbabc", b"abcba"]);
+        assert_eq!(find_palindromes(b"abc", 0).count(), 0);
+        assert_eq!(find_palindromes(b"abc", 1).count(), 0);
+        assert_eq!(find_palindromes(b"abc", 4).count(), 0);
+    }
+
+    #[test]
+    fn mirrors() {
+        assert_eq!(mirror(b"aba"), b"bab");
+        assert_eq!(mirror(b"abcba"), b"cbabc");
+        assert_eq!(mirror(b"abcdcba"), b"dcbabcd");
+    }
+
+    #[test]
+    fn declared_protocols() {
+        let abcba = Protocol { len: 5, rule: Rule::Mirrored };
+        let supports = |txt: &str, protocol: &Protocol| txt.parse::<Ipv7Addr>().unwrap().supports(protocol);
+        assert!(supports("abcba[cbabc]", &abcba));
+        assert!(supports("xyz[qcbabcq]zzabcbazz", &abcba));
+        assert!(!supports("abcba[abcba]", &abcba));
+        assert!(!supports("aba[bab]", &abcba));
+        let abccba = Protocol { len: 6, rule: Rule::SupernetOnly };
+        assert!(supports("abccba[xyz]", &abccba));
+        assert!(!supports("abccba[xyzzyx]", &abccba));
+    }
+
+    #[test]
+    fn tls_examples() {
+        for (txt, tls) in [("abba[mnop]qrst", true), ("abcd[bddb]xyyx", false), ("aaaa[qwer]tyui", false),
+                           ("ioxxoj[asdfgh]zxcvbn", true)] {
+            assert_eq!(txt.parse::<Ipv7Addr>().unwrap().supports_tls(), tls, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn ssl_examples() {
+        for (txt, ssl) in [("aba[bab]xyz", true), ("xyx[xyx]xyx", false), ("aaa[kek]eke", true),
+                           ("zazbz[bzb]cdb", true)] {
+            assert_eq!(txt.parse::<Ipv7Addr>().unwrap().supports_ssl(), ssl, "{}", txt);
+        }
+    }
+
+    /// For every byte of `addr`, whether it is inside square brackets.
+    fn reference_inside(addr: &[u8]) -> Vec<bool> {
+        let mut inside = false;
+        addr.iter().map(|&ch| {
+            if ch == b'[' {
+                inside = true;
+            } else if ch == b']' {
+                inside = false;
+            }
+            inside
+        }).collect()
+    }
+
+    /// Brute force TLS check working on the raw text, independent of
+    /// `Ipv7Addr` and `find_palindromes`.
+    fn reference_tls(addr: &str) -> bool {
+        let b = addr.as_bytes();
+        let inside = reference_inside(b);
+        let mut outside_abba = false;
+        for i in 0..b.len().saturating_sub(3) {
+            if b[i..i+4].iter().all(|ch| ch.is_ascii_lowercase())
+               && b[i] == b[i+3] && b[i+1] == b[i+2] && b[i] != b[i+1] {
+                if inside[i] {
+                    return false;
+                }
+                outside_abba = true;
+            }
+        }
+        outside_abba
+    }
+
+    /// Brute force SSL check comparing every ABA outside with every ABA inside
+    /// square brackets.
+    fn reference_ssl(addr: &str) -> bool {
+        let b = addr.as_bytes();
+        let inside = reference_inside(b);
+        let abas: Vec<usize> = (0..b.len().saturating_sub(2)).filter(|&i| {
+            b[i..i+3].iter().all(|ch| ch.is_ascii_lowercase())
+                && b[i] == b[i+2] && b[i] != b[i+1]
+        }).collect();
+        abas.iter().filter(|&&i| !inside[i]).any(|&i| {
+            abas.iter().filter(|&&j| inside[j]).any(|&j| b[i] == b[j+1] && b[i+1] == b[j])
+        })
+    }
+
+    /// Random addresses are classified like the reference implementation,
+    /// and forced protocols are supported.
+    #[test]
+    fn generated_addresses_match_reference() {
+        let mut rng = StdRng::seed_from_u64(2016);
+        for _i in 0..20_000 {
+            let force = [Force::Nothing, Force::Tls, Force::Ssl][rng.gen_range(0..3)];
+            let segments = rng.gen_range(min_segments(force)..=9);
+            let txt = generate_address(&mut rng, segments, force);
+            let addr = txt.parse::<Ipv7Addr>().unwrap_or_else(|e| panic!("{}: {}", txt, e));
+            assert_eq!(addr.supports_tls(), reference_tls(&txt), "TLS of {}", txt);
+            assert_eq!(addr.supports_ssl(), reference_ssl(&txt), "SSL of {}", txt);
+            assert!(force != Force::Tls || addr.supports_tls(), "{} forced TLS", txt);
+            assert!(force != Force::Ssl || addr.supports_ssl(), "{} forced SSL", txt);
+        }
+    }
+}
@@ -75,25 +75,112 @@
 //! 
 //! Answer: CFLELOYFCS
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Index;
 use std::path::Path;
 
+use aoc2016::grid::render_bits;
+
 fn read_data<P>(name: P) -> Vec<String>
 where P: AsRef<Path> {
     let file = File::open(name).unwrap();
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum CMD {
     RECT(u8, u8),
     ROTROW(u8, u8),
     ROTCOL(u8, u8),
 }
 
-type GRID = [[bool; 50]; 6];
+impl fmt::Display for CMD {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CMD::RECT(c, r) => write!(f, "rect {}x{}", c, r),
+            CMD::ROTROW(r, n) => write!(f, "rotate row y={} by {}", r, n),
+            CMD::ROTCOL(c, n) => write!(f, "rotate column x={} by {}", c, n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ScreenError {
+    /// the command addresses pixels outside of a screen of the given size
+    OutOfBounds(CMD, usize, usize),
+    /// the rows of a screen of the given size can't all be addressed with
+    /// the `u8` arguments of the commands
+    TooLarge(usize, usize),
+}
+
+impl fmt::Display for ScreenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScreenError::OutOfBounds(cmd, w, h) => write!(f, "{:?} doesn't fit on a {}x{} screen", cmd, w, h),
+            ScreenError::TooLarge(w, h) => write!(f, "a {}x{} screen has too many rows for the commands", w, h),
+        }
+    }
+}
+
+/// A screen of `width` x `height` pixels, stored row by row.
+/// `screen[row][col]` gives a single pixel.
+#[derive(Debug, Clone, PartialEq)]
+struct Screen {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Index<usize> for Screen {
+    type Output = [bool];
+
+    fn index(&self, row: usize) -> &[bool] {
+        &self.pixels[row * self.width..(row + 1) * self.width]
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render_bits(self.width, self.height, '#', '.', |x, y| self[y][x]))
+    }
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Screen {
+        Screen { width, height, pixels: vec![false; width * height] }
+    }
+
+    fn apply(&mut self, cmd: CMD) -> Result<(), ScreenError> {
+        let (w, h) = (self.width, self.height);
+        match cmd {
+            CMD::RECT(c, r) if c as usize <= w && r as usize <= h => {
+                for a in 0..r as usize {
+                    self.pixels[a * w..a * w + c as usize].fill(true);
+                }
+            },
+            // a row of a zero width screen can't be rotated
+            CMD::ROTROW(r, n) if (r as usize) < h && w > 0 => {
+                self.pixels[r as usize * w..(r as usize + 1) * w].rotate_right(n as usize % w);
+            },
+            CMD::ROTCOL(c, n) if (c as usize) < w => {
+                let tmp: Vec<bool> = (0..h).map(|i| self[i][c as usize]).collect();
+                for (i, p) in tmp.into_iter().enumerate() {
+                    self.pixels[(i + n as usize) % h * w + c as usize] = p;
+                }
+            },
+            _ => return Err(ScreenError::OutOfBounds(cmd, w, h)),
+        }
+        Ok(())
+    }
 
+    fn count_pixels(&self) -> usize {
+        self.pixels.iter().filter(|&&p| p).count()
+    }
+}
 
 fn decode_command(cmd: &str) -> CMD {
     let mut elm = cmd.split_whitespace();
@@ -107,7 +194,7 @@ fn decode_command(cmd: &str) -> CMD {
         Some("rotate") => {
             let c = elm.next().unwrap();
             let a = elm.next().unwrap().split_once('=')
-                            .map(|(r,c)| c.parse::<u8>().unwrap()).unwrap();
+                            .map(|(_r,c)| c.parse::<u8>().unwrap()).unwrap();
             let b = elm.last().unwrap().parse::<u8>().unwrap();
             match c {
                 "row" => CMD::ROTROW(a, b),
@@ -120,170 +207,449 @@ fn decode_command(cmd: &str) -> CMD {
     }
 }
 
-fn draw_display(data: &Vec<String>) ->  GRID {
-    let mut grid: GRID = [[false; 50]; 6];
+fn draw_display(data: &[String], width: usize, height: usize) -> Result<Screen, ScreenError> {
+    let mut screen = Screen::new(width, height);
     for raw_cmd in data.iter() {
-        println!("{:?}", decode_command(raw_cmd));        
-        match decode_command(raw_cmd) {
-            CMD::RECT(c,r) => { for a in 0..r as usize { for b in 0..c as usize { grid[a][b] = true; }}},
-            CMD::ROTROW(c, w) => {  
-                let g = grid[c as usize];
-                for i in 0..50 as usize {
-                    grid[c as usize][(i+w as usize) % 50] = g[i];
-                }
-            },
-            CMD::ROTCOL(c, w ) => {
-                let mut tmp: [bool; 6] = [false; 6];
-                for i in 0..6 as usize {
-                    tmp[i] = grid[i][c as usize];
-                }
-                for i in 0..6 as usize {
-                    grid[(i+w as usize) % 6][c as usize] = tmp[i];
-                }
-            },
+        screen.apply(decode_command(raw_cmd))?;
+    }
+    Ok(screen)
+}
+
+/// Like `draw_display`, but keeps the screen after every command.
+fn trace_display(data: &[String], width: usize, height: usize) -> Result<Vec<(Option<CMD>, Screen)>, ScreenError> {
+    let mut screen = Screen::new(width, height);
+    let mut result: Vec<(Option<CMD>, Screen)> = vec![(None, screen.clone())];
+    for raw_cmd in data.iter() {
+        let cmd = decode_command(raw_cmd);
+        screen.apply(cmd)?;
+        result.push((Some(cmd), screen.clone()));
+    }
+    Ok(result)
+}
+
+/// The command leading to a frame, the first frame shows the empty screen.
+fn frame_label(cmd: &Option<CMD>) -> String {
+    cmd.map_or("empty screen".to_string(), |c| c.to_string())
+}
+
+/// Writes every frame as plain PBM image `dir/frame_NNN.pbm`.
+fn write_pbm(frames: &[(Option<CMD>, Screen)], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (n, (cmd, screen)) in frames.iter().enumerate() {
+        let mut out = BufWriter::new(File::create(dir.join(format!("frame_{:03}.pbm", n)))?);
+        writeln!(out, "P1\n# {}\n{} {}", frame_label(cmd), screen.width, screen.height)?;
+        for row in 0..screen.height {
+            let line: Vec<&str> = screen[row].iter().map(|&p| if p { "1" } else { "0" }).collect();
+            writeln!(out, "{}", line.join(" "))?;
         }
     }
-    grid
+    Ok(())
 }
 
-fn read_screen(grid: &GRID) -> String {
-    let mut result: String = String::new();
-    let mut col: usize = 0;
-    while col < 50 {
-        if grid[0][col] == false {
-            if grid[1][col] == false {
-                if grid[4][col] == false {
-                    result.push('J');
-                } else {
-                    result.push('I');
-                }
-                col += 5;
-            } else {
-                if grid[5][col] == false {
-                    col += 3;
-                    if grid[5][col] == false {
-                        if grid[3][col] == false {
-                            result.push('C');
-                        } else {
-                            if grid[2][col] == false {
-                                result.push('G');
-                            } else {
-                                result.push('O');
-                            }
-                        }
-                    } else {
-                        result.push('Q');
-                    }
-                    col += 2;
-                } else {
-                    if grid[4][col] == false {
-                        result.push('S');
-                    } else {
-                        result.push('A');
-                    }
-                    col += 5;
-                }
+/// Pixels of the screen are drawn as squares of this size in the GIF.
+const GIF_SCALE: usize = 8;
+
+/// Writes all frames into one animated GIF, looping forever.
+fn write_gif(frames: &[(Option<CMD>, Screen)], path: &Path) -> io::Result<()> {
+    let Some((_cmd, first)) = frames.first() else { return Ok(()) };
+    let (width, height) = (first.width * GIF_SCALE, first.height * GIF_SCALE);
+    let palette: [u8; 6] = [0x0f, 0x0f, 0x23, 0xff, 0xff, 0x66];
+    let mut encoder = gif::Encoder::new(BufWriter::new(File::create(path)?), width as u16, height as u16, &palette)
+        .map_err(io::Error::other)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+    for (_cmd, screen) in frames {
+        let buffer: Vec<u8> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| screen[y / GIF_SCALE][x / GIF_SCALE] as u8))
+            .collect();
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay: 5,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        };
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Steps through the commands and writes the frames as `ascii` art to
+/// stdout, as `pbm` images into a directory or as one animated `gif`.
+fn trace(data: &[String], format: &str, target: Option<&str>) {
+    let frames = match trace_display(data, 50, 6) {
+        Ok(frames) => frames,
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = match format {
+        "ascii" => {
+            for (n, (cmd, screen)) in frames.iter().enumerate() {
+                println!("{:3}: {}\n{}", n, frame_label(cmd), screen);
             }
-        } else {
-            if grid[1][col] == false { 
-                if grid[5][col] == false {
-                    result.push('T');
-                } else {
-                    result.push('Z');
-                }
-                col += 5;
-            } else {
-                if grid[2][col] == false {
-                    if grid[5][col] == false {
-                        result.push('Y');
-                    } else {
-                        result.push('X');
-                    }
-                    col += 5;
-                } else {
-                    if grid[4][col] == false {
-                        result.push('V');
-                        col += 5;
-                    } else {
-                        if grid[5][col] == false {
-                            result.push('U');
-                            col += 5;
-                        } else {
-                            col += 1;
-                            if grid[0][col] == false {
-                                if grid[5][col] == true {
-                                    result.push('L');
-                                } else if grid[4][col] == true {
-                                    result.push('W');
-                                } else if grid[3][col] == true {
-                                    result.push('K');
-                                } else if grid[2][col] == true {
-                                    result.push('H');
-                                } else {
-                                    if grid[5][col+2] == false {
-                                        result.push('M');
-                                    } else {
-                                        result.push('N');
-                                    }
-                                }
-                                col += 4;
-                            } else {
-                                if grid[2][col] == false {
-                                    if grid[5][col] == false {
-                                        col += 2;
-                                        if grid[5][col] == false {
-                                            result.push('P');
-                                        } else {
-                                            result.push('R');
-                                        }
-                                        col += 2;
-                                    } else {
-                                        result.push('D');
-                                        col += 4;
-                                    } 
-                                } else {
-                                    if grid[5][col] == false {
-                                        result.push('F');
-                                        col += 4;
-                                    } else {
-                                        col += 2;
-                                        if grid[5][col] == false {
-                                            result.push('B');
-                                        } else {
-                                            result.push('E');
-                                        }
-                                        col += 2;
-                                    } 
-                                }
-                            }
-
-                        }
-                    }
+            Ok(())
+        },
+        "pbm" => write_pbm(&frames, Path::new(target.unwrap_or("trace"))),
+        "gif" => write_gif(&frames, Path::new(target.unwrap_or("trace.gif"))),
+        _ => {
+            println!("Unknown trace format {:?}, use ascii, pbm or gif", format);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = result {
+        println!("Error writing trace: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// A bitmap font for the letters the screen can show. Every glyph row is a
+/// string of `#` and `.`; glyphs are placed in cells of `cell` columns, the
+/// columns right of a glyph are blank.
+struct Font {
+    width: usize,
+    height: usize,
+    cell: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+/// The font of this puzzle: letters of 5x6 pixels, most of them only use
+/// the left four columns.
+const FONT_5X6: Font = Font { width: 5, height: 6, cell: 5, glyphs: &[
+    ('A', &[".##..", "#..#.", "#..#.", "####.", "#..#.", "#..#."]),
+    ('B', &["###..", "#..#.", "###..", "#..#.", "#..#.", "###.."]),
+    ('C', &[".##..", "#..#.", "#....", "#....", "#..#.", ".##.."]),
+    ('E', &["####.", "#....", "###..", "#....", "#....", "####."]),
+    ('F', &["####.", "#....", "###..", "#....", "#....", "#...."]),
+    ('G', &[".##..", "#..#.", "#....", "#.##.", "#..#.", ".###."]),
+    ('H', &["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."]),
+    ('I', &[".###.", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', &["..##.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', &["#..#.", "#.#..", "##...", "#.#..", "#.#..", "#..#."]),
+    ('L', &["#....", "#....", "#....", "#....", "#....", "####."]),
+    ('O', &[".##..", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('P', &["###..", "#..#.", "#..#.", "###..", "#....", "#...."]),
+    ('R', &["###..", "#..#.", "#..#.", "###..", "#.#..", "#..#."]),
+    ('S', &[".###.", "#....", "#....", ".##..", "...#.", "###.."]),
+    ('U', &["#..#.", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["####.", "...#.", "..#..", ".#...", "#....", "####."]),
+]};
+
+/// The larger font used by other AoC puzzles: letters of 6x10 pixels with
+/// two blank columns between them.
+const FONT_6X10: Font = Font { width: 6, height: 10, cell: 8, glyphs: &[
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#",
+            "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######",
+            "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.",
+            "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....",
+            "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#",
+            "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..",
+            "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..",
+            "..#...", ".#....", "#.....", "#.....", "######"]),
+]};
+
+const FONTS: [&Font; 2] = [&FONT_5X6, &FONT_6X10];
 
+#[derive(Debug, PartialEq)]
+enum OcrError {
+    /// there is no font for screens of this height
+    NoFont(usize),
+    /// the pixels of the n-th letter match no glyph of the font
+    UnknownGlyph(usize, String),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::NoFont(h) => write!(f, "no font for a screen {} pixels tall", h),
+            OcrError::UnknownGlyph(n, pattern) => write!(f, "unknown glyph at letter {}:\n{}", n, pattern),
+        }
+    }
+}
+
+impl Font {
+    /// Whether the cell starting at column `col` shows the glyph `rows`.
+    /// Pixels right of the glyph and beyond the screen count as dark.
+    fn matches(&self, screen: &Screen, col: usize, rows: &[&str]) -> bool {
+        (0..self.height).all(|r| (0..self.cell).all(|c| {
+            let lit = col + c < screen.width && screen[r][col + c];
+            let glyph = c < self.width && rows[r].as_bytes()[c] == b'#';
+            lit == glyph
+        }))
+    }
+
+    /// The pixels of the cell starting at column `col` as `#`/`.` rows.
+    fn pattern(&self, screen: &Screen, col: usize) -> String {
+        let end = (col + self.cell).min(screen.width);
+        (0..self.height)
+            .map(|r| screen[r][col..end].iter().map(|&p| if p { '#' } else { '.' }).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Draws `text` on a screen just large enough for it, blanks stay
+    /// dark. Returns the first character without a glyph as error.
+    fn render(&self, text: &str) -> Result<Screen, char> {
+        let mut screen = Screen::new(self.cell * text.chars().count(), self.height);
+        for (n, ch) in text.chars().enumerate() {
+            if ch == ' ' {
+                continue;
+            }
+            let (_ch, rows) = self.glyphs.iter().find(|(g, _rows)| *g == ch).ok_or(ch)?;
+            for (r, row) in rows.iter().enumerate() {
+                for (c, p) in row.bytes().enumerate() {
+                    screen.pixels[r * screen.width + n * self.cell + c] = p == b'#';
                 }
+            }
+        }
+        Ok(screen)
+    }
+}
 
-            } 
+/// Decodes the letters on the screen by matching every cell against the
+/// glyphs of the font for the screen's height. Blank cells become spaces.
+fn read_screen(screen: &Screen) -> Result<String, OcrError> {
+    let font = FONTS.iter().find(|f| f.height == screen.height)
+        .ok_or(OcrError::NoFont(screen.height))?;
+    let mut result: String = String::new();
+    for (n, col) in (0..screen.width).step_by(font.cell).enumerate() {
+        if (0..font.height).all(|r| !screen[r][col..(col + font.cell).min(screen.width)].contains(&true)) {
+            result.push(' ');
+            continue;
         }
+        match font.glyphs.iter().find(|(_ch, rows)| font.matches(screen, col, rows)) {
+            Some((ch, _rows)) => result.push(*ch),
+            None => return Err(OcrError::UnknownGlyph(n, font.pattern(screen, col))),
+        }
+    }
+    Ok(result)
+}
+
+/// Pushes `rotate(step)` for steps of at most 255 adding up to `n`, as
+/// rotations by more than fit into the `u8` of a command.
+fn push_rotations<F: Fn(u8) -> CMD>(result: &mut Vec<CMD>, rotate: F, mut n: usize) {
+    while n > 0 {
+        let step = n.min(u8::MAX as usize);
+        result.push(rotate(step as u8));
+        n -= step;
     }
-    println!("{:?}", grid);
-    result
 }
 
-fn count_pixels(grid: &GRID) -> u32 {
-    let mut result:u32 = 0;
-    for r in 0..6 {
-        for c in 0..50 {
-            if grid[r][c] {
-                result += 1;
+/// Generates commands that draw `target` on an empty screen of the same
+/// size. Column by column from the right, the pattern is built in column 0
+/// with `rect 1xB` and `rotate column`, rows are rotated right only when
+/// the next lit pixel needs column 0 of that row or it has to be cleared.
+fn compile_screen(target: &Screen) -> Result<Vec<CMD>, ScreenError> {
+    let too_large = |_| ScreenError::TooLarge(target.width, target.height);
+    let mut result: Vec<CMD> = Vec::new();
+    // the target column currently shown in column 0 of each row, None
+    // while the row has no lit pixel yet
+    let mut offset: Vec<Option<usize>> = vec![None; target.height];
+    for x in (0..target.width).rev() {
+        for (r, o) in offset.iter_mut().enumerate() {
+            match *o {
+                None if target[r][x] => *o = Some(x),
+                Some(col) if target[r][x] || target[r][col] => {
+                    let row = u8::try_from(r).map_err(too_large)?;
+                    push_rotations(&mut result, |n| CMD::ROTROW(row, n), col - x);
+                    *o = Some(x);
+                },
+                _ => (),
             }
         }
+        // column 0 is dark now, place the runs of lit pixels bottom up
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for r in 0..target.height {
+            if target[r][x] {
+                match runs.last_mut() {
+                    Some((start, len)) if *start + *len == r => *len += 1,
+                    _ => runs.push((r, 1)),
+                }
+            }
+        }
+        let mut top: usize = 0;
+        for (i, &(start, len)) in runs.iter().rev().enumerate() {
+            if i > 0 {
+                push_rotations(&mut result, |n| CMD::ROTCOL(0, n), top - start);
+            }
+            result.push(CMD::RECT(1, u8::try_from(len).map_err(too_large)?));
+            top = start;
+        }
+        push_rotations(&mut result, |n| CMD::ROTCOL(0, n), top);
+    }
+    for (r, o) in offset.iter().enumerate() {
+        if let Some(col) = *o {
+            let row = u8::try_from(r).map_err(too_large)?;
+            push_rotations(&mut result, |n| CMD::ROTROW(row, n), col);
+        }
+    }
+    Ok(result)
+}
+
+/// Prints the commands drawing `text` in the 5x6 font, after checking them
+/// with `draw_display` and `read_screen`.
+fn compile_text(text: &str) {
+    let target = match FONT_5X6.render(text) {
+        Ok(screen) => screen,
+        Err(ch) => {
+            println!("Error: no glyph for {:?}", ch);
+            std::process::exit(1);
+        }
+    };
+    let cmds: Vec<String> = match compile_screen(&target) {
+        Ok(cmds) => cmds.iter().map(|c| c.to_string()).collect(),
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let screen = draw_display(&cmds, target.width, target.height).unwrap();
+    if screen != target || read_screen(&screen).as_deref() != Ok(text) {
+        println!("Error: the commands draw\n{}instead of\n{}", screen, target);
+        std::process::exit(1);
+    }
+    for cmd in &cmds {
+        println!("{}", cmd);
     }
-    result
+    eprintln!("{} commands for a {}x{} screen", cmds.len(), target.width, target.height);
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--compile") {
+        compile_text(args.get(i + 1).map(|t| t.as_str()).unwrap_or(""));
+        return;
+    }
     let data = read_data("data/08/input.txt");
-    let grid = draw_display(&data);
-    println!("Part 1: {}", count_pixels(&grid));
-    println!("Part 2: {}", read_screen(&grid));
-}
\ No newline at end of file
+    if let Some(i) = args.iter().position(|a| a == "--trace") {
+        let format = args.get(i + 1).map(|t| t.as_str()).unwrap_or("ascii");
+        trace(&data, format, args.get(i + 2).map(|t| t.as_str()));
+        return;
+    }
+    let screen = match draw_display(&data, 50, 6) {
+        Ok(screen) => screen,
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("Part 1: {}", screen.count_pixels());
+    match read_screen(&screen) {
+        Ok(txt) => println!("Part 2: {}", txt),
+        Err(e) => println!("Part 2: {}\n{}", e, screen),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from the puzzle description on a 7x3 screen.
+    #[test]
+    fn example() {
+        let data: Vec<String> = ["rect 3x2", "rotate column x=1 by 1", "rotate row y=0 by 4", "rotate column x=1 by 1"]
+            .iter().map(|x| x.to_string()).collect();
+        let screen = draw_display(&data, 7, 3).unwrap();
+        assert_eq!(screen.to_string(), ".#..#.#\n#.#....\n.#.....\n");
+        assert_eq!(screen.count_pixels(), 6);
+    }
+
+    #[test]
+    fn zero_size_screen() {
+        let mut screen = Screen::new(0, 3);
+        assert_eq!(screen.apply(CMD::ROTROW(0, 1)), Err(ScreenError::OutOfBounds(CMD::ROTROW(0, 1), 0, 3)));
+        assert_eq!(screen.apply(CMD::RECT(0, 3)), Ok(()));
+        let mut screen = Screen::new(3, 0);
+        assert_eq!(screen.apply(CMD::ROTCOL(0, 1)), Ok(()));
+        assert_eq!(screen.apply(CMD::ROTROW(0, 1)), Err(ScreenError::OutOfBounds(CMD::ROTROW(0, 1), 3, 0)));
+    }
+
+    #[test]
+    fn trace_starts_empty() {
+        let data: Vec<String> = ["rect 3x2", "rotate column x=1 by 1"].iter().map(|x| x.to_string()).collect();
+        let frames = trace_display(&data, 7, 3).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], (None, Screen::new(7, 3)));
+        assert_eq!(frames[2], (Some(CMD::ROTCOL(1, 1)), draw_display(&data, 7, 3).unwrap()));
+    }
+
+    fn round_trip(text: &str) {
+        let target = FONT_5X6.render(text).unwrap();
+        let cmds: Vec<String> = compile_screen(&target).unwrap().iter().map(|c| c.to_string()).collect();
+        let screen = draw_display(&cmds, target.width, target.height).unwrap();
+        assert_eq!(screen, target);
+        assert_eq!(read_screen(&screen).as_deref(), Ok(text));
+    }
+
+    #[test]
+    fn compile_round_trip() {
+        round_trip("CFLELOYFCS");
+    }
+
+    /// Rotations by more than 255 columns are split.
+    #[test]
+    fn compile_wide_screen() {
+        round_trip(&format!("A{}A", " ".repeat(60)));
+    }
+
+    #[test]
+    fn read_every_glyph() {
+        for font in FONTS {
+            let text: String = font.glyphs.iter().map(|(ch, _rows)| *ch).collect();
+            for text in [text.clone(), format!("{} {}", &text[..2], &text[2..])] {
+                let screen = font.render(&text).unwrap();
+                assert_eq!(screen.height, font.height);
+                assert_eq!(read_screen(&screen), Ok(text));
+            }
+        }
+        assert_eq!(FONT_6X10.render("AQ"), Err('Q'));
+    }
+
+    #[test]
+    fn read_unsupported_height() {
+        assert_eq!(read_screen(&Screen::new(10, 7)), Err(OcrError::NoFont(7)));
+    }
+
+    #[test]
+    fn read_unknown_glyph() {
+        let mut screen = FONT_5X6.render("CFL").unwrap();
+        // the blank column right of the F's top bar
+        screen.pixels[9] = true;
+        let pattern = "#####\n#....\n###..\n#....\n#....\n#....".to_string();
+        assert_eq!(read_screen(&screen), Err(OcrError::UnknownGlyph(1, pattern)));
+    }
+
+    #[test]
+    fn compile_too_many_rows() {
+        let target = Screen::new(1, 300);
+        assert_eq!(compile_screen(&target), Ok(Vec::new()));
+        let mut target = Screen::new(1, 300);
+        target.apply(CMD::RECT(1, 1)).unwrap();
+        target.apply(CMD::ROTCOL(0, 255)).unwrap();
+        target.apply(CMD::ROTCOL(0, 20)).unwrap();
+        assert_eq!(compile_screen(&target), Err(ScreenError::TooLarge(1, 300)));
+    }
+}
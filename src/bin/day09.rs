@@ -63,72 +63,911 @@
 //! 
 //! Answer:10774309173
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read};
 use std::path::Path;
 
+/// The file as it is, whitespace is removed by the `Tokenizer`.
 fn read_data<P>(name: P) -> String
 where P: AsRef<Path> {
-    let file = File::open(name).unwrap();
-    String::from_iter(BufReader::new(file).lines().map(|x| x.unwrap()))
-}
-
-fn unpack(input: &str) -> String {
-    let mut result: String = String::new(); 
-    let mut ipt = input.chars();
-    while let Some(ch) = &ipt.next() {
-        if *ch == '(' {
-            let mut tmp:String = String::new();
-            while let Some(ch) = &ipt.next() {
-                if *ch == ')' {
-                    break;
-                } else {
-                    tmp.push(*ch);
+    fs::read_to_string(name).unwrap()
+}
+
+/// What the lengths in a marker count: bytes or (UTF-8 encoded) characters.
+/// Both are the same for ASCII files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Bytes,
+    Chars,
+}
+
+impl Unit {
+    /// Length in bytes of the unit starting with byte `b`.
+    fn width(self, b: u8) -> usize {
+        match (self, b) {
+            (Unit::Bytes, _) => 1,
+            (Unit::Chars, 0xf0..) => 4,
+            (Unit::Chars, 0xe0..) => 3,
+            (Unit::Chars, 0xc0..) => 2,
+            (Unit::Chars, _) => 1,
+        }
+    }
+
+    /// Number of units in `data`.
+    fn count(self, data: &[u8]) -> usize {
+        match self {
+            Unit::Bytes => data.len(),
+            Unit::Chars => data.iter().filter(|&&b| b & 0xc0 != 0x80).count(),
+        }
+    }
+}
+
+/// Problems in the compressed data, with the byte offset of the marker.
+/// Offsets inside the data of a marker don't count whitespace.
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    /// the marker has no closing parenthesis
+    UnterminatedMarker(usize),
+    /// the text between the parentheses isn't of the form `AxB`
+    MalformedMarker(usize, String),
+    /// the marker wants more units of data (the first number) than follow
+    /// before the input ends (the second)
+    TruncatedData(usize, usize, usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnterminatedMarker(pos) => write!(f, "marker at {} is not terminated", pos),
+            ParseError::MalformedMarker(pos, txt) => write!(f, "malformed marker ({}) at {}", txt, pos),
+            ParseError::TruncatedData(pos, len, found) =>
+                write!(f, "marker at {} repeats {} units, but only {} follow", pos, len, found),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Parses the `AxB` between the parentheses of the marker at `offset`
+/// into length and repeat count.
+fn parse_marker(spec: &[u8], offset: usize) -> Result<(usize, u64), ParseError> {
+    let txt: String = String::from_utf8_lossy(spec).split_whitespace().collect();
+    txt.split_once('x')
+        .and_then(|(a, b)| Some((a.parse::<usize>().ok()?, b.parse::<u64>().ok()?)))
+        .ok_or(ParseError::MalformedMarker(offset, txt))
+}
+
+/// The marker starting with the `(` at `start` of `input`, which begins at
+/// offset `base` of the whole file: its length, repeat count and the
+/// position after its `)`.
+fn marker_at(input: &[u8], start: usize, base: usize) -> Result<(usize, u64, usize), ParseError> {
+    let offset = base + start;
+    let Some(close) = input[start..].iter().position(|&b| b == b')') else {
+        return Err(ParseError::UnterminatedMarker(offset));
+    };
+    let (len, repeats) = parse_marker(&input[start + 1..start + close], offset)?;
+    Ok((len, repeats, start + close + 1))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// data outside of markers, without whitespace
+    Literal(&'a [u8]),
+    /// a marker at `offset` and the `len` units following it, without
+    /// whitespace; these start at `data_offset`
+    Marker { offset: usize, len: usize, repeats: u64, data: Cow<'a, [u8]>, data_offset: usize },
+}
+
+/// Splits compressed data into literals and markers. Whitespace is
+/// skipped as the format demands.
+struct Tokenizer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    base: usize,
+    unit: Unit,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a [u8], unit: Unit) -> Tokenizer<'a> {
+        Tokenizer::with_offset(input, unit, 0)
+    }
+
+    /// A tokenizer for `input` found at offset `base` of the whole file.
+    fn with_offset(input: &'a [u8], unit: Unit, base: usize) -> Tokenizer<'a> {
+        Tokenizer { input, pos: 0, base, unit }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn marker(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let offset = self.base + start;
+        let (len, repeats, end) = marker_at(self.input, start, self.base)?;
+        self.pos = end;
+        self.skip_whitespace();
+        let data_start = self.pos;
+        let mut data: Cow<'a, [u8]> = Cow::Borrowed(&[]);
+        for found in 0..len {
+            self.skip_whitespace();
+            let end = self.pos + self.input.get(self.pos).map_or(1, |&b| self.unit.width(b));
+            // a character cut off at the end is missing, too
+            if end > self.input.len() {
+                return Err(ParseError::TruncatedData(offset, len, found));
+            }
+            // stay borrowed as long as no whitespace had to be skipped
+            if matches!(data, Cow::Borrowed(_)) && self.pos == data_start + data.len() {
+                data = Cow::Borrowed(&self.input[data_start..end]);
+            } else {
+                data.to_mut().extend_from_slice(&self.input[self.pos..end]);
+            }
+            self.pos = end;
+        }
+        Ok(Token::Marker { offset, len, repeats, data, data_offset: self.base + data_start })
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.input.get(start) {
+            None => None,
+            Some(b'(') => {
+                let result = self.marker(start);
+                if result.is_err() {
+                    // nothing sensible follows a broken marker
+                    self.pos = self.input.len();
+                }
+                Some(result)
+            },
+            Some(_) => {
+                self.pos = self.input[start..].iter()
+                    .position(|&b| b == b'(' || b.is_ascii_whitespace())
+                    .map_or(self.input.len(), |p| start + p);
+                Some(Ok(Token::Literal(&self.input[start..self.pos])))
+            },
+        }
+    }
+}
+
+fn unpack(input: &[u8], unit: Unit) -> Result<Vec<u8>, ParseError> {
+    let mut result: Vec<u8> = Vec::new();
+    for token in Tokenizer::new(input, unit) {
+        match token? {
+            Token::Literal(txt) => result.extend_from_slice(txt),
+            Token::Marker { repeats, data, .. } => {
+                for _i in 0..repeats {
+                    result.extend_from_slice(&data);
+                }
+            },
+        }
+    }
+    Ok(result)
+}
+
+/// Decompresses version 2 data into memory, only for small outputs.
+fn unpack_recursive(input: &[u8], unit: Unit) -> Result<Vec<u8>, ParseError> {
+    unpack_tokens(Tokenizer::new(input, unit))
+}
+
+fn unpack_tokens(tokens: Tokenizer) -> Result<Vec<u8>, ParseError> {
+    let unit = tokens.unit;
+    let mut result: Vec<u8> = Vec::new();
+    for token in tokens {
+        match token? {
+            Token::Literal(txt) => result.extend_from_slice(txt),
+            Token::Marker { repeats, data, data_offset, .. } => {
+                let section = unpack_tokens(Tokenizer::with_offset(&data, unit, data_offset))?;
+                for _i in 0..repeats {
+                    result.extend_from_slice(&section);
                 }
+            },
+        }
+    }
+    Ok(result)
+}
+
+fn count_recursive_unpack(input: &[u8], unit: Unit) -> Result<u64, ParseError> {
+    count_tokens(Tokenizer::new(input, unit))
+}
+
+fn count_tokens(tokens: Tokenizer) -> Result<u64, ParseError> {
+    let unit = tokens.unit;
+    let mut result: u64 = 0;
+    for token in tokens {
+        result += match token? {
+            Token::Literal(txt) => unit.count(txt) as u64,
+            Token::Marker { repeats, data, data_offset, .. } =>
+                repeats * count_tokens(Tokenizer::with_offset(&data, unit, data_offset))?,
+        };
+    }
+    Ok(result)
+}
+
+/// The structure of version 2 data: every marker is a node containing the
+/// nodes of its data section.
+#[derive(Debug, PartialEq)]
+enum Node<'a> {
+    /// data outside of markers, `size` units long
+    Literal { data: &'a [u8], size: u64 },
+    /// the marker at `offset` covering `len` units, repeated `count` times
+    /// for an expanded size of `size` units
+    Repeat { offset: usize, len: usize, count: u64, size: u64, children: Vec<Node<'a>> },
+}
+
+impl Node<'_> {
+    fn size(&self) -> u64 {
+        match self {
+            Node::Literal { size, .. } | Node::Repeat { size, .. } => *size,
+        }
+    }
+
+    /// Number of nested markers, 0 for a literal.
+    fn depth(&self) -> usize {
+        match self {
+            Node::Literal { .. } => 0,
+            Node::Repeat { children, .. } => 1 + children.iter().map(|c| c.depth()).max().unwrap_or(0),
+        }
+    }
+}
+
+/// Builds the marker tree of the whole input in one pass, without copying
+/// any data.
+struct TreeBuilder<'a> {
+    input: &'a [u8],
+    pos: usize,
+    unit: Unit,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn build(input: &'a [u8], unit: Unit) -> Result<Vec<Node<'a>>, ParseError> {
+        TreeBuilder { input, pos: 0, unit }.nodes(None, 0)
+    }
+
+    /// Parses nodes until `budget` units are used up or, without a budget,
+    /// until the input ends. `owner` is the offset of the enclosing marker.
+    fn nodes(&mut self, mut budget: Option<usize>, owner: usize) -> Result<Vec<Node<'a>>, ParseError> {
+        let len = budget.unwrap_or(0);
+        let mut result: Vec<Node<'a>> = Vec::new();
+        loop {
+            while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
             }
-            let (c, n) = tmp.split_once('x').map(|(a,b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap())).unwrap();
-            tmp.clear();
-            for _i in 0..c {
-                tmp.push(ipt.next().unwrap());
+            match budget {
+                Some(0) => break,
+                Some(b) if self.pos == self.input.len() => return Err(ParseError::TruncatedData(owner, len, len - b)),
+                None if self.pos == self.input.len() => break,
+                _ => (),
             }
-            for _i in 0..n {
-                result.push_str(&tmp);
+            let start = self.pos;
+            if self.input[start] == b'(' {
+                let (child_len, count, end) = marker_at(self.input, start, 0)?;
+                self.pos = end;
+                if let Some(b) = budget {
+                    // the marker itself is part of the enclosing data
+                    let marker: Vec<u8> = self.input[start..self.pos].iter()
+                        .filter(|b| !b.is_ascii_whitespace()).copied().collect();
+                    let used = self.unit.count(&marker) + child_len;
+                    if used > b {
+                        return Err(ParseError::TruncatedData(start, child_len, b.saturating_sub(used - child_len)));
+                    }
+                    budget = Some(b - used);
+                }
+                let children = self.nodes(Some(child_len), start)?;
+                let size = count.saturating_mul(children.iter().map(|c| c.size()).fold(0, u64::saturating_add));
+                result.push(Node::Repeat { offset: start, len: child_len, count, size, children });
+            } else {
+                let mut size: u64 = 0;
+                while self.pos < self.input.len() && budget != Some(0)
+                      && self.input[self.pos] != b'(' && !self.input[self.pos].is_ascii_whitespace() {
+                    self.pos = (self.pos + self.unit.width(self.input[self.pos])).min(self.input.len());
+                    budget = budget.map(|b| b - 1);
+                    size += 1;
+                }
+                result.push(Node::Literal { data: &self.input[start..self.pos], size });
             }
-        } else {
-            result.push(*ch);
         }
+        Ok(result)
     }
+}
+
+/// The `n` markers producing the largest part of the output, as pairs of
+/// the output size and the marker. A nested marker counts as often as its
+/// enclosing markers repeat it.
+fn top_markers<'t, 'a>(nodes: &'t [Node<'a>], n: usize) -> Vec<(u64, &'t Node<'a>)> {
+    fn collect<'t, 'a>(nodes: &'t [Node<'a>], factor: u64, result: &mut Vec<(u64, &'t Node<'a>)>) {
+        for node in nodes {
+            if let Node::Repeat { count, children, .. } = node {
+                result.push((factor.saturating_mul(node.size()), node));
+                collect(children, factor.saturating_mul(*count), result);
+            }
+        }
+    }
+    let mut result: Vec<(u64, &Node)> = Vec::new();
+    collect(nodes, 1, &mut result);
+    result.sort_by_key(|r| std::cmp::Reverse(r.0));
+    result.truncate(n);
     result
 }
 
-fn count_recursive_unpack(input: &str) -> u64 {
-    let mut result: u64 = 0;
-    let mut ipt = input.chars();
-    while let Some(ch) = ipt.next() {
-        if ch == '(' {
-            let mut tmp:String = String::new();
-            while let Some(ch) = ipt.next() {
-                if ch == ')' {
-                    break;
-                } else {
-                    tmp.push(ch);
+/// Prints one line per node, the data of markers nested deeper than
+/// `max_depth` is left out.
+fn print_outline(nodes: &[Node], level: usize, max_depth: usize) {
+    let indent = 2 * (level + 1);
+    for node in nodes {
+        match node {
+            Node::Literal { data, size } => {
+                let txt = String::from_utf8_lossy(data);
+                let short: String = txt.chars().take(40).collect();
+                let ellipsis = if short.len() < txt.len() { "..." } else { "" };
+                println!("{:indent$}{}{}  [{}]", "", short, ellipsis, size, indent = indent);
+            },
+            Node::Repeat { offset, len, count, size, children } => {
+                println!("{:indent$}({}x{}) @{}  [{}]", "", len, count, offset, size, indent = indent);
+                if level + 1 < max_depth {
+                    print_outline(children, level + 1, max_depth);
+                } else if !children.is_empty() {
+                    println!("{:indent$}...", "", indent = indent + 2);
+                }
+            },
+        }
+    }
+}
+
+/// `day09 --tree [depth] [file]` shows the marker tree of version 2 data.
+fn inspect(data: &[u8], unit: Unit, max_depth: usize) {
+    let nodes = match TreeBuilder::build(data, unit) {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let total = nodes.iter().map(|n| n.size()).fold(0, u64::saturating_add);
+    println!("{} units expand to {}, markers nested up to {} deep",
+             unit.count(data), total, nodes.iter().map(|n| n.depth()).max().unwrap_or(0));
+    // a marker's share includes the markers nested in it, so they overlap
+    println!("Largest contributions, including nested markers:");
+    for (size, node) in top_markers(&nodes, 10) {
+        if let Node::Repeat { offset, len, count, .. } = node {
+            println!("  ({}x{}) @{}: {} ({:.1}% incl.)", len, count, offset, size, 100.0 * size as f64 / total as f64);
+        }
+    }
+    println!("Outline:");
+    print_outline(&nodes, 0, max_depth);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Version {
+    /// markers inside repeated data are copied as they are
+    V1,
+    /// markers inside repeated data are decompressed, too
+    V2,
+}
+
+/// Data that still has to be written `repeats` times, `pos` is the next
+/// byte to process. Only the data of version 2 markers is decompressed
+/// further, literals and version 1 data are `raw`.
+#[derive(Debug)]
+struct Frame {
+    data: Vec<u8>,
+    pos: usize,
+    repeats: u64,
+    raw: bool,
+}
+
+/// How many bytes are read from the input at a time.
+const CHUNK: usize = 8192;
+
+/// Decompresses the data read from `input` on the fly. Only the input not
+/// yet tokenized and the data sections of the currently open markers are
+/// kept in memory, so even version 2 output of many gigabytes can be
+/// streamed. Errors inside repeated data are reported at the input offset
+/// the decompressor has reached.
+struct Decompressor<R: Read> {
+    input: R,
+    eof: bool,
+    /// input read so far, the part before `pos` is processed
+    pending: Vec<u8>,
+    pos: usize,
+    /// offset of `pending[0]` in the input
+    offset: usize,
+    version: Version,
+    unit: Unit,
+    stack: Vec<Frame>,
+}
+
+impl<R: Read> Decompressor<R> {
+    fn new(input: R, version: Version, unit: Unit) -> Decompressor<R> {
+        Decompressor {
+            input, eof: false, pending: Vec::new(), pos: 0, offset: 0,
+            version, unit, stack: Vec::new(),
+        }
+    }
+
+    /// Reads the next chunk of input, dropping what is processed.
+    fn fill(&mut self) -> io::Result<()> {
+        self.pending.drain(..self.pos);
+        self.offset += self.pos;
+        self.pos = 0;
+        let mut chunk = [0; CHUNK];
+        let n = self.input.read(&mut chunk)?;
+        self.eof = n == 0;
+        self.pending.extend_from_slice(&chunk[..n]);
+        Ok(())
+    }
+
+    /// The next literal or marker of the input as frame, reading more input
+    /// while a marker is incomplete.
+    fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        loop {
+            let mut tokens = Tokenizer::with_offset(&self.pending[self.pos..], self.unit, self.offset + self.pos);
+            let frame = match tokens.next() {
+                None if self.eof => return Ok(None),
+                None | Some(Err(ParseError::UnterminatedMarker(_) | ParseError::TruncatedData(..))) if !self.eof => {
+                    self.fill()?;
+                    continue;
+                },
+                None => unreachable!(),
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(Token::Literal(txt))) => Frame { data: txt.to_vec(), pos: 0, repeats: 1, raw: true },
+                Some(Ok(Token::Marker { repeats, data, .. })) =>
+                    Frame { data: data.into_owned(), pos: 0, repeats, raw: self.version == Version::V1 },
+            };
+            self.pos += tokens.pos;
+            return Ok(Some(frame));
+        }
+    }
+
+    fn push(&mut self, frame: Frame) {
+        if frame.repeats > 0 && !frame.data.is_empty() {
+            self.stack.push(frame);
+        }
+    }
+}
+
+impl<R: Read> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n: usize = 0;
+        while n < buf.len() {
+            let Some(frame) = self.stack.last_mut() else {
+                match self.next_frame()? {
+                    None => break,
+                    Some(frame) => self.push(frame),
+                }
+                continue;
+            };
+            if frame.pos == frame.data.len() {
+                frame.repeats -= 1;
+                frame.pos = 0;
+                if frame.repeats == 0 {
+                    self.stack.pop();
                 }
+                continue;
             }
-            let (c, n) = tmp.split_once('x').map(|(a,b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap())).unwrap();
-            // by_ref erlaugt es, &mut weiter zu reichen, ohne den Owner zu ändern
-            // hier werden c Zeichen in count_sub verarbeitet und ipt weitergeschaltet 
-            result += n as u64 * count_recursive_unpack(&String::from_iter(&mut ipt.by_ref().take(c as usize)));
+            let rest = &frame.data[frame.pos..];
+            let literal = if frame.raw {
+                rest
+            } else {
+                let mut tokens = Tokenizer::with_offset(rest, self.unit, self.offset + self.pos);
+                match tokens.next() {
+                    Some(Ok(Token::Literal(txt))) => txt,
+                    Some(Ok(Token::Marker { repeats, data, .. })) => {
+                        let data = data.into_owned();
+                        frame.pos += tokens.pos;
+                        self.push(Frame { data, pos: 0, repeats, raw: false });
+                        continue;
+                    },
+                    Some(Err(e)) => return Err(e.into()),
+                    None => {
+                        frame.pos = frame.data.len();
+                        continue;
+                    },
+                }
+            };
+            // copy literal data up to the next marker
+            let k = literal.len().min(buf.len() - n);
+            buf[n..n + k].copy_from_slice(&literal[..k]);
+            frame.pos += k;
+            n += k;
+        }
+        Ok(n)
+    }
+}
+
+/// `day09 --decompress 1|2 [file]` writes the decompressed file to stdout.
+fn decompress(version: &str, name: &str, unit: Unit) -> io::Result<u64> {
+    let version = match version {
+        "1" => Version::V1,
+        "2" => Version::V2,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown version {}", version))),
+    };
+    let mut reader = Decompressor::new(File::open(name)?, version, unit);
+    io::copy(&mut reader, &mut BufWriter::new(io::stdout().lock()))
+}
+
+#[derive(Debug, PartialEq)]
+enum CompressError {
+    /// the character at this position can't be written in the format
+    Unencodable(usize, char),
+}
+
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompressError::Unencodable(pos, ch) => write!(f, "{:?} at {} can't be encoded", ch, pos),
+        }
+    }
+}
+
+/// Number of decimal digits of `n`.
+fn digits(n: usize) -> usize {
+    n.checked_ilog10().map_or(1, |d| d as usize + 1)
+}
+
+/// The repetition starting at `pos` that saves the most characters as
+/// `(length, repeats)`, if any saves at least one.
+fn best_repeat(txt: &[char], pos: usize) -> Option<(usize, usize)> {
+    let rest = &txt[pos..];
+    let mut result: Option<(usize, usize)> = None;
+    let mut best_saving: usize = 0;
+    for len in 1..=rest.len() / 2 {
+        let unit = &rest[..len];
+        let repeats = 1 + rest[len..].chunks_exact(len).take_while(|c| *c == unit).count();
+        let marker = digits(len) + digits(repeats) + 3;
+        let saving = (repeats * len).saturating_sub(marker + len);
+        if saving > best_saving {
+            best_saving = saving;
+            result = Some((len, repeats));
+        }
+    }
+    result
+}
+
+/// Characters that can't be part of an input to the compressor: the
+/// decompressor ignores whitespace, and in version 2 every `(` starts a
+/// marker.
+fn check_encodable(txt: &[char], version: Version) -> Result<(), CompressError> {
+    match txt.iter().position(|ch| ch.is_whitespace() || (version == Version::V2 && *ch == '(')) {
+        Some(pos) => Err(CompressError::Unencodable(pos, txt[pos])),
+        None => Ok(()),
+    }
+}
+
+/// Greedily replaces repeated substrings by markers, so that
+/// `unpack(compress_v1(s, unit)?.as_bytes(), unit)? == s.as_bytes()`.
+/// A run of literal characters containing `(` is wrapped in a `(Nx1)`
+/// marker to not be taken for a marker itself.
+fn compress_v1(input: &str, unit: Unit) -> Result<String, CompressError> {
+    let txt: Vec<char> = input.chars().collect();
+    check_encodable(&txt, Version::V1)?;
+    let mut result = String::new();
+    let mut literal = String::new();
+    let mut pos: usize = 0;
+    while pos < txt.len() {
+        if let Some((len, repeats)) = best_repeat(&txt, pos) {
+            push_literal(&mut result, &mut literal, unit);
+            let section = String::from_iter(&txt[pos..pos + len]);
+            result.push_str(&format!("({}x{})", unit.count(section.as_bytes()), repeats));
+            result.push_str(&section);
+            pos += len * repeats;
+        } else {
+            literal.push(txt[pos]);
+            pos += 1;
+        }
+    }
+    push_literal(&mut result, &mut literal, unit);
+    // a single marker around everything is the worst case
+    let wrapped = format!("({}x1){}", unit.count(input.as_bytes()), input);
+    Ok(if wrapped.len() < result.len() { wrapped } else { result })
+}
+
+/// Moves the pending literal characters to the result, wrapped in a marker
+/// if one of them would start a marker.
+fn push_literal(result: &mut String, literal: &mut String, unit: Unit) {
+    if literal.contains('(') {
+        result.push_str(&format!("({}x1)", unit.count(literal.as_bytes())));
+    }
+    result.push_str(literal);
+    literal.clear();
+}
+
+/// Like `compress_v1`, but the repeated data is compressed again, which
+/// gives nested markers. Input must not contain `(`.
+fn compress_v2(input: &str, unit: Unit) -> Result<String, CompressError> {
+    let txt: Vec<char> = input.chars().collect();
+    check_encodable(&txt, Version::V2)?;
+    Ok(compress_nested(&txt, unit))
+}
+
+fn compress_nested(txt: &[char], unit: Unit) -> String {
+    let mut result = String::new();
+    let mut pos: usize = 0;
+    while pos < txt.len() {
+        if let Some((len, repeats)) = best_repeat(txt, pos) {
+            let section = compress_nested(&txt[pos..pos + len], unit);
+            result.push_str(&format!("({}x{})", unit.count(section.as_bytes()), repeats));
+            result.push_str(&section);
+            pos += len * repeats;
         } else {
-            result += 1;
+            result.push(txt[pos]);
+            pos += 1;
         }
     }
     result
 }
 
+/// `day09 --compress 1|2 [file]` writes the compressed file to stdout and
+/// reports the compression ratio after checking the round trip.
+fn compress(version: &str, name: &str, unit: Unit) {
+    let data: String = read_data(name).split_whitespace().collect();
+    let (result, restored) = match version {
+        "1" => compress_v1(&data, unit).map(|c| { let r = unpack(c.as_bytes(), unit); (c, r) }),
+        "2" => compress_v2(&data, unit).map(|c| { let r = unpack_recursive(c.as_bytes(), unit); (c, r) }),
+        _ => {
+            eprintln!("Error: unknown version {}", version);
+            std::process::exit(1);
+        }
+    }.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+    if restored.as_deref() != Ok(data.as_bytes()) {
+        eprintln!("Error: {} doesn't decompress to the input", result);
+        std::process::exit(1);
+    }
+    println!("{}", result);
+    let (before, after) = (data.chars().count(), result.chars().count());
+    eprintln!("{} -> {} characters, ratio {:.3}", before, after, after as f64 / before.max(1) as f64);
+}
+
+/// `day09 [--chars] [file]` solves both parts, marker lengths count bytes
+/// unless `--chars` is given.
 fn main() {
-    let data = read_data("data/09/input.txt");
-    let txt = unpack(&data);
-    println!("Part 1: {}", txt.len());
-    println!("Part 2: {}", count_recursive_unpack(&data));
+    let args: Vec<String> = std::env::args().collect();
+    let unit = if args.iter().any(|a| a == "--chars") { Unit::Chars } else { Unit::Bytes };
+    if let Some(i) = args.iter().position(|a| a == "--decompress") {
+        let version = args.get(i + 1).map(|v| v.as_str()).unwrap_or("2");
+        let name = args.get(i + 2).map(|v| v.as_str()).unwrap_or("data/09/input.txt");
+        if let Err(e) = decompress(version, name, unit) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(i) = args.iter().position(|a| a == "--compress") {
+        let version = args.get(i + 1).map(|v| v.as_str()).unwrap_or("1");
+        compress(version, args.get(i + 2).map(|v| v.as_str()).unwrap_or("data/09/input.txt"), unit);
+        return;
+    }
+    if let Some(i) = args.iter().position(|a| a == "--tree") {
+        let depth = args.get(i + 1).and_then(|d| d.parse::<usize>().ok());
+        let name = args.get(i + 1 + depth.is_some() as usize).map(|v| v.as_str());
+        inspect(read_data(name.unwrap_or("data/09/input.txt")).as_bytes(), unit, depth.unwrap_or(2));
+        return;
+    }
+    let name = args.iter().skip(1).find(|a| !a.starts_with("--")).map(|a| a.as_str());
+    let data = read_data(name.unwrap_or("data/09/input.txt"));
+    match unpack(data.as_bytes(), unit) {
+        Ok(txt) => println!("Part 1: {}", unit.count(&txt)),
+        Err(e) => println!("Part 1: {}", e),
+    }
+    match count_recursive_unpack(data.as_bytes(), unit) {
+        Ok(len) => println!("Part 2: {}", len),
+        Err(e) => println!("Part 2: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn round_trip(input: &str) {
+        for unit in [Unit::Bytes, Unit::Chars] {
+            let packed = compress_v1(input, unit).unwrap();
+            assert_eq!(unpack(packed.as_bytes(), unit).unwrap(), input.as_bytes(), "V1 {:?} of {:?}", unit, input);
+            if !input.contains('(') {
+                let packed = compress_v2(input, unit).unwrap();
+                assert_eq!(unpack_recursive(packed.as_bytes(), unit).unwrap(), input.as_bytes(),
+                           "V2 {:?} of {:?}", unit, input);
+            }
+        }
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        for input in ["", "A", "ADVENT", "ABABABABAB", "X(8x2)(3x3)ABCY", "((((((((", "éçéçéçéçXYZXYZXYZ", "ééééé(é"] {
+            round_trip(input);
+        }
+        let mut rng = StdRng::seed_from_u64(2016);
+        for _i in 0..500 {
+            let len = rng.gen_range(0..60);
+            let input: String = (0..len).map(|_| ['A', 'B', 'x', '(', ')', '1', 'é'][rng.gen_range(0..7)]).collect();
+            round_trip(&input);
+        }
+    }
+
+    const V2_EXAMPLES: [(&str, u64); 4] = [
+        ("(3x3)XYZ", 9),
+        ("X(8x2)(3x3)ABCY", 20),
+        ("(27x12)(20x12)(13x14)(7x10)(1x12)A", 241920),
+        ("(25x3)(3x3)ABC(2x3)XY(5x2)PQRSTX(18x9)(3x2)TWO(5x7)SEVEN", 445),
+    ];
+
+    fn tree_size(nodes: &[Node]) -> u64 {
+        nodes.iter().map(|n| n.size()).sum()
+    }
+
+    #[test]
+    fn tree_examples() {
+        for (input, size) in V2_EXAMPLES {
+            let nodes = TreeBuilder::build(input.as_bytes(), Unit::Bytes).unwrap();
+            assert_eq!(tree_size(&nodes), size, "{}", input);
+            assert_eq!(count_recursive_unpack(input.as_bytes(), Unit::Bytes), Ok(size), "{}", input);
+        }
+        let nodes = TreeBuilder::build(b"(27x12)(20x12)(13x14)(7x10)(1x12)A", Unit::Bytes).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].depth(), 5);
+    }
+
+    #[test]
+    fn tree_matches_count() {
+        let mut rng = StdRng::seed_from_u64(2016);
+        let mut checked = 0;
+        for _i in 0..5000 {
+            let input: String = (0..rng.gen_range(0..12))
+                .map(|_| match rng.gen_range(0..4) {
+                    0 => format!("({}x{})", rng.gen_range(0..8), rng.gen_range(0..4)),
+                    1 => " ".to_string(),
+                    2 => "é".to_string(),
+                    _ => "AB".to_string(),
+                })
+                .collect();
+            for unit in [Unit::Bytes, Unit::Chars] {
+                if let Ok(size) = count_recursive_unpack(input.as_bytes(), unit) {
+                    let nodes = TreeBuilder::build(input.as_bytes(), unit).unwrap();
+                    assert_eq!(tree_size(&nodes), size, "{:?} {:?}", unit, input);
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 1000, "only {} valid inputs", checked);
+    }
 
+    #[test]
+    fn top_markers_weighted() {
+        let input = b"(25x3)(3x3)ABC(2x3)XY(5x2)PQRSTX(18x9)(3x2)TWO(5x7)SEVEN";
+        let nodes = TreeBuilder::build(input, Unit::Bytes).unwrap();
+        let top: Vec<(u64, usize)> = top_markers(&nodes, 4).into_iter()
+            .map(|(size, node)| match node {
+                Node::Repeat { offset, .. } => (size, *offset),
+                Node::Literal { .. } => panic!("literal {:?}", node),
+            })
+            .collect();
+        // the (5x7) only makes 35 units, but its enclosing marker repeats it 9 times
+        assert_eq!(top, [(369, 32), (315, 46), (75, 0), (54, 38)]);
+    }
+
+    fn tokens(input: &str, unit: Unit) -> Vec<Result<Token<'_>, ParseError>> {
+        Tokenizer::new(input.as_bytes(), unit).collect()
+    }
+
+    #[test]
+    fn tokenize_whitespace() {
+        let marker = |offset, len, repeats, data: &'static str, data_offset| {
+            Ok(Token::Marker { offset, len, repeats, data: Cow::Borrowed(data.as_bytes()), data_offset })
+        };
+        assert_eq!(tokens("A (1x\n 5)\n B C\n", Unit::Bytes),
+                   [Ok(Token::Literal(b"A")), marker(2, 1, 5, "B", 11), Ok(Token::Literal(b"C"))]);
+        // whitespace inside the data is dropped, too
+        assert_eq!(tokens("(3x2)A B\nCD", Unit::Bytes),
+                   [marker(0, 3, 2, "ABC", 5), Ok(Token::Literal(b"D"))]);
+        assert_eq!(unpack(b"A (1x\n 5)\n B C\n", Unit::Bytes), Ok(b"ABBBBBC".to_vec()));
+    }
+
+    #[test]
+    fn tokenize_errors() {
+        assert_eq!(tokens("AB(1y2)C", Unit::Bytes),
+                   [Ok(Token::Literal(b"AB")), Err(ParseError::MalformedMarker(2, "1y2".to_string()))]);
+        assert_eq!(tokens(" (1 x)", Unit::Bytes), [Err(ParseError::MalformedMarker(1, "1x".to_string()))]);
+        assert_eq!(tokens("AB(3x3", Unit::Bytes), [Ok(Token::Literal(b"AB")), Err(ParseError::UnterminatedMarker(2))]);
+        assert_eq!(tokens("XY(5x2)A B\nC", Unit::Bytes),
+                   [Ok(Token::Literal(b"XY")), Err(ParseError::TruncatedData(2, 5, 3))]);
+        // a character cut off counts as missing
+        assert_eq!(tokens("(2x2)A\u{f6}", Unit::Chars)[0], Ok(Token::Marker {
+            offset: 0, len: 2, repeats: 2, data: Cow::Borrowed("A\u{f6}".as_bytes()), data_offset: 5 }));
+        assert_eq!(Tokenizer::new(&"(2x2)A\u{f6}".as_bytes()[..7], Unit::Chars).next(),
+                   Some(Err(ParseError::TruncatedData(0, 2, 1))));
+        // offsets inside version 2 data point into the whole input
+        assert_eq!(unpack_recursive(b"(8x2)AB(9x1)CD", Unit::Bytes), Err(ParseError::TruncatedData(7, 9, 1)));
+    }
+
+    #[test]
+    fn bytes_and_chars() {
+        let data = fs::read("data/09/test.txt").unwrap();
+        let bytes = unpack(&data, Unit::Bytes).unwrap();
+        let chars = unpack(&data, Unit::Chars).unwrap();
+        // the ö is outside of any marker, only the count differs
+        assert_eq!(bytes, chars);
+        assert_eq!(Unit::Bytes.count(&bytes), 113);
+        assert_eq!(Unit::Chars.count(&chars), 111);
+        for unit in [Unit::Bytes, Unit::Chars] {
+            assert_eq!(count_recursive_unpack(&data, unit), Err(ParseError::TruncatedData(31, 12, 0)));
+        }
+        // inside a marker the unit decides what is repeated
+        assert_eq!(unpack("(2x2)\u{f6}x".as_bytes(), Unit::Bytes), Ok("\u{f6}\u{f6}x".as_bytes().to_vec()));
+        assert_eq!(unpack("(2x2)\u{f6}x".as_bytes(), Unit::Chars), Ok("\u{f6}x\u{f6}x".as_bytes().to_vec()));
+    }
+
+    /// Hands out the input one byte per `read`, so the decompressor has to
+    /// refill in the middle of every marker.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn stream(input: &str, version: Version, unit: Unit) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        Decompressor::new(Trickle(input.as_bytes()), version, unit).read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn decompressor_matches_unpack() {
+        let inputs = ["ADVENT", "A(1x5)BC", "(3x3)XYZ", "A(2x2)BCD(2x2)EFG", "(6x1)(1x3)A", "X(8x2)(3x3)ABCY",
+                      "(27x12)(20x12)(13x14)(7x10)(1x12)A", "(25x3)(3x3)ABC(2x3)XY(5x2)PQRSTX(18x9)(3x2)TWO(5x7)SEVEN",
+                      "(3x2)é(x)A(2x2)ééB", "(0x5)(2x0)ABC", "(4x2)(1x2)éX"];
+        for input in inputs {
+            for unit in [Unit::Bytes, Unit::Chars] {
+                // both fail on the same inputs, e.g. a marker cut in half by version 2
+                assert_eq!(stream(input, Version::V1, unit).ok(), unpack(input.as_bytes(), unit).ok(),
+                           "V1 {:?} of {:?}", unit, input);
+                assert_eq!(stream(input, Version::V2, unit).ok(), unpack_recursive(input.as_bytes(), unit).ok(),
+                           "V2 {:?} of {:?}", unit, input);
+            }
+        }
+    }
+
+    fn stream_error(input: &str, version: Version, unit: Unit) -> ParseError {
+        let e = stream(input, version, unit).unwrap_err();
+        *e.into_inner().unwrap().downcast::<ParseError>().unwrap()
+    }
+
+    #[test]
+    fn decompressor_reports_errors() {
+        assert_eq!(stream_error("(5x2)AB", Version::V1, Unit::Bytes), ParseError::TruncatedData(0, 5, 2));
+        assert_eq!(stream_error("(9x2)(1x3)é", Version::V2, Unit::Chars), ParseError::TruncatedData(0, 9, 6));
+        assert_eq!(stream_error("AB(3x", Version::V1, Unit::Bytes), ParseError::UnterminatedMarker(2));
+        assert_eq!(stream_error("AB(3y1)", Version::V1, Unit::Bytes), ParseError::MalformedMarker(2, "3y1".to_string()));
+    }
+
+    #[test]
+    fn compress_never_grows_much() {
+        let input = "A(1x5)BC".repeat(20);
+        let packed = compress_v1(&input, Unit::Bytes).unwrap();
+        assert!(packed.len() <= input.len() + 6, "{}", packed);
+        assert_eq!(compress_v2("ABCABCABCABC", Unit::Bytes).unwrap(), "(3x4)ABC");
+    }
 }
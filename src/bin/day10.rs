@@ -51,13 +51,14 @@
 //! 
 //! Answer: 12567
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 enum Destination {
     #[default]
     None,
@@ -65,18 +66,101 @@ enum Destination {
     Bin(u32)
 }
 
+impl Destination {
+    /// The name of the destination as Graphviz node.
+    fn dot_id(&self) -> String {
+        match self {
+            Destination::Bot(id) => format!("bot{}", id),
+            Destination::Bin(id) => format!("output{}", id),
+            Destination::None => "nowhere".to_string(),
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 struct Control {
     data_a: Option<u32>,
     data_b: Option<u32>,
     dest_high: Destination,
     dest_low: Destination,
+    /// number of rules given for the bot
+    rules: u32,
+    /// number of chips handed to the bot
+    received: u32,
+}
+
+/// Problems found by `Factory::validate`.
+#[derive(Debug, PartialEq)]
+enum ConfigError {
+    /// the bot gets chips, but there is no rule what to do with them
+    NoRule(u32),
+    /// there is more than one rule for the bot, the last one wins
+    DuplicateRule(u32),
+    /// the rule of the first bot passes chips to the second one, which
+    /// has no rule
+    UnknownTarget(u32, u32),
+    /// the bot got more than two chips, later ones replace earlier ones
+    TooManyChips(u32, u32),
+    /// the bot never got two chips and so never passed any on
+    NeverFired(u32),
+    /// the chips were still moving after this many steps
+    StepLimit(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NoRule(bot) => write!(f, "bot {} gets chips, but has no rule", bot),
+            ConfigError::DuplicateRule(bot) => write!(f, "bot {} has more than one rule", bot),
+            ConfigError::UnknownTarget(bot, target) =>
+                write!(f, "bot {} gives to bot {}, which has no rule", bot, target),
+            ConfigError::TooManyChips(bot, n) => write!(f, "bot {} receives {} chips", bot, n),
+            ConfigError::NeverFired(bot) => write!(f, "bot {} never compares two chips", bot),
+            ConfigError::StepLimit(n) => write!(f, "chips still moving after {} steps", n),
+        }
+    }
+}
+
+/// What happened in the factory, in the order it happened.
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    /// a `value` instruction hands a chip to a bot
+    Input { value: u32, bot: u32 },
+    /// a bot holding two chips compares them
+    Compare { bot: u32, low: u32, high: u32 },
+    /// a bot passes a chip on
+    Give { bot: u32, value: u32, to: Destination },
+}
+
+impl Event {
+    /// The event as a single line JSON object.
+    fn to_json(&self) -> String {
+        match self {
+            Event::Input { value, bot } =>
+                format!(r#"{{"event":"input","value":{},"bot":{}}}"#, value, bot),
+            Event::Compare { bot, low, high } =>
+                format!(r#"{{"event":"compare","bot":{},"low":{},"high":{}}}"#, bot, low, high),
+            Event::Give { bot, value, to } => {
+                let to = match to {
+                    Destination::Bot(id) => format!(r#"{{"bot":{}}}"#, id),
+                    Destination::Bin(id) => format!(r#"{{"output":{}}}"#, id),
+                    Destination::None => "null".to_string(),
+                };
+                format!(r#"{{"event":"give","bot":{},"value":{},"to":{}}}"#, bot, value, to)
+            },
+        }
+    }
 }
 
 #[derive(Default, Debug)]
 struct Factory {
     bots: HashMap<u32, Control>,
     bins: HashMap<u32, u32>,
+    /// chips waiting in input bins as (value, bot taking it)
+    inputs: VecDeque<(u32, u32)>,
+    /// bots holding two chips, in the order they got the second one
+    ready: VecDeque<u32>,
+    events: Vec<Event>,
 }
 
 impl Factory {
@@ -84,70 +168,191 @@ impl Factory {
         Factory {
             bots: HashMap::new(),
             bins: HashMap::new(),
+            inputs: VecDeque::new(),
+            ready: VecDeque::new(),
+            events: Vec::new(),
         }
     }
 
-    fn eval_bot(&mut self, bot: u32) {
-        let bot_ctl = self.bots.get(&bot).unwrap().clone();
-        if let Some(data_a) = bot_ctl.data_a {
-            if let Some(data_b) = bot_ctl.data_b {
-                let high_val = data_a.max(data_b); 
-                let low_val = data_a.min(data_b);
-                match bot_ctl.dest_high {
-                    Destination::Bot(id) => { self.update_bot(id, high_val); },
-                    Destination::Bin(id) => { self.bins.insert(id, high_val); },
-                    Destination::None => (),
-                }
-                match bot_ctl.dest_low {
-                    Destination::Bot(id) => { self.update_bot(id, low_val); },
-                    Destination::Bin(id) => { self.bins.insert(id, low_val); },
-                    Destination::None => (),
-                }
+    /// Puts a chip into an input bin, to be taken by the bot when the
+    /// factory runs.
+    fn add_input(&mut self, bot: u32, val: u32) {
+        self.inputs.push_back((val, bot));
+    }
+
+    fn configure_bot(&mut self, bot: u32, dest_high: Destination, dest_low: Destination) {
+        let bot_ctl = self.bots.entry(bot).or_default();
+        bot_ctl.dest_high = dest_high;
+        bot_ctl.dest_low = dest_low;
+        bot_ctl.rules += 1;
+    }
+
+    fn update_bot(&mut self, bot: u32, val: u32) {
+        let bot_ctl = self.bots.entry(bot).or_default();
+        bot_ctl.received += 1;
+        if bot_ctl.data_a.is_none() {
+            bot_ctl.data_a = Some(val);
+        } else {
+            if bot_ctl.data_b.is_none() && bot_ctl.rules > 0 {
+                self.ready.push_back(bot);
             }
+            // a third chip replaces the second, validate() reports it
+            bot_ctl.data_b = Some(val);
         }
     }
 
-    fn update_bot(&mut self, bot: u32, val: u32) {
-        if let Some(bot_ctl) = self.bots.get_mut(&bot) {
-            if bot_ctl.data_a == None {
-                bot_ctl.data_a = Some(val);
-            } else {   
-                bot_ctl.data_b = Some(val);
+    fn deliver(&mut self, dest: Destination, val: u32) {
+        match dest {
+            Destination::Bot(id) => self.update_bot(id, val),
+            Destination::Bin(id) => { self.bins.insert(id, val); },
+            Destination::None => (),
+        }
+    }
+
+    /// The bot compares its chips and passes them on.
+    fn fire(&mut self, bot: u32) {
+        let bot_ctl = self.bots.get_mut(&bot).unwrap();
+        let (Some(data_a), Some(data_b)) = (bot_ctl.data_a.take(), bot_ctl.data_b.take()) else {
+            return;
+        };
+        let (dest_high, dest_low) = (bot_ctl.dest_high, bot_ctl.dest_low);
+        let high_val = data_a.max(data_b);
+        let low_val = data_a.min(data_b);
+        self.events.push(Event::Compare { bot, low: low_val, high: high_val });
+        self.events.push(Event::Give { bot, value: high_val, to: dest_high });
+        self.deliver(dest_high, high_val);
+        self.events.push(Event::Give { bot, value: low_val, to: dest_low });
+        self.deliver(dest_low, low_val);
+    }
+
+    /// Moves the chips until no bot holds two of them any more. Bots take
+    /// the chips from the input bins one at a time, in the order of the
+    /// instructions, and every bot ready after that fires before the next
+    /// chip is taken. Each taken chip and each firing bot is a step.
+    fn run(&mut self, step_limit: usize) -> Result<usize, ConfigError> {
+        let mut steps: usize = 0;
+        loop {
+            if self.ready.is_empty() && self.inputs.is_empty() {
+                return Ok(steps);
             }
-            self.eval_bot(bot);
-        } else {
-            self.bots.insert(bot, Control { data_a: Some(val), .. Default::default() });
+            if steps == step_limit {
+                return Err(ConfigError::StepLimit(steps));
+            }
+            if let Some(bot) = self.ready.pop_front() {
+                self.fire(bot);
+            } else if let Some((val, bot)) = self.inputs.pop_front() {
+                self.events.push(Event::Input { value: val, bot });
+                self.update_bot(bot, val);
+            }
+            steps += 1;
         }
     }
 
-    fn configure_bot(&mut self, bot: u32, dest_high: Destination, dest_low: Destination) {
-        if let Some(bot_ctl) = self.bots.get_mut(&bot) {
-            bot_ctl.dest_high = dest_high;
-            bot_ctl.dest_low = dest_low;
-            self.eval_bot(bot);
-        } else {
-            self.bots.insert(bot, Control { dest_high, dest_low, .. Default::default() });
+    /// The bot that compared the two values, if any did.
+    fn part_1(&self, val_a: u32, val_b: u32) -> Option<u32> {
+        let (low, high) = (val_a.min(val_b), val_a.max(val_b));
+        self.events.iter().find_map(|e| match e {
+            Event::Compare { bot, low: l, high: h } if (*l, *h) == (low, high) => Some(*bot),
+            _ => None,
+        })
+    }
+
+    fn write_events(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        for event in &self.events {
+            writeln!(out, "{}", event.to_json())?;
         }
+        out.flush()
+    }
 
+    /// The wiring of the bots in Graphviz DOT format. Inputs and output bins
+    /// are drawn as boxes, the way of the chip with value `chip` in red.
+    fn to_dot(&self, chip: Option<u32>) -> String {
+        // the edges the chip travels along as pairs of node names
+        let mut path: HashSet<(String, String)> = HashSet::new();
+        for event in &self.events {
+            match event {
+                Event::Input { value, bot } if Some(*value) == chip =>
+                    { path.insert((format!("value{}", value), format!("bot{}", bot))); },
+                Event::Give { bot, value, to } if Some(*value) == chip =>
+                    { path.insert((format!("bot{}", bot), to.dot_id())); },
+                _ => (),
+            }
+        }
+        let style = |from: &str, to: &str| {
+            if path.contains(&(from.to_string(), to.to_string())) { ", color=red, penwidth=2" } else { "" }
+        };
+        let mut result = String::from("digraph factory {\n    rankdir=LR;\n    node [shape=circle];\n");
+        let mut bins: Vec<u32> = self.bots.values()
+            .flat_map(|ctl| [&ctl.dest_low, &ctl.dest_high])
+            .filter_map(|dest| match dest { Destination::Bin(id) => Some(*id), _ => None })
+            .chain(self.bins.keys().copied())
+            .collect();
+        bins.sort();
+        bins.dedup();
+        for id in bins {
+            result.push_str(&format!("    output{} [shape=box, label=\"output {}\"];\n", id, id));
+        }
+        for event in &self.events {
+            if let Event::Input { value, bot } = event {
+                let from = format!("value{}", value);
+                result.push_str(&format!("    {} [shape=plaintext, label=\"value {}\"];\n", from, value));
+                result.push_str(&format!("    {} -> bot{} [style=dashed{}];\n", from, bot, style(&from, &format!("bot{}", bot))));
+            }
+        }
+        let mut bots: Vec<(&u32, &Control)> = self.bots.iter().collect();
+        bots.sort_by_key(|(id, _ctl)| **id);
+        for (id, ctl) in bots {
+            let from = format!("bot{}", id);
+            result.push_str(&format!("    {} [label=\"{}\"];\n", from, id));
+            for (label, dest) in [("low", &ctl.dest_low), ("high", &ctl.dest_high)] {
+                if *dest != Destination::None {
+                    let to = dest.dot_id();
+                    result.push_str(&format!("    {} -> {} [label={}{}];\n", from, to, label, style(&from, &to)));
+                }
+            }
+        }
+        result.push_str("}\n");
+        result
     }
 
-    fn part_1(&self, val_a: u32, val_b: u32) -> u32 {
-        //println!("bots: {:?}", self.bots);
-        //println!("bins: {:?}", self.bins);
-        for (bot, ctl) in self.bots.iter() {
-            if let Some(data_a) = ctl.data_a {
-                if let Some(data_b) = ctl.data_b {
-                    if (data_a == val_a && data_b == val_b) || (data_a == val_b && data_b == val_a) {
-                        return *bot;
+    /// Checks the configuration after all instructions were processed and
+    /// returns every problem found, ordered by bot.
+    fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let fired: HashSet<u32> = self.events.iter()
+            .filter_map(|e| match e { Event::Compare { bot, .. } => Some(*bot), _ => None })
+            .collect();
+        let mut bots: Vec<(&u32, &Control)> = self.bots.iter().collect();
+        bots.sort_by_key(|(id, _ctl)| **id);
+        let mut result: Vec<ConfigError> = Vec::new();
+        for (&id, ctl) in bots {
+            if ctl.rules == 0 && ctl.received > 0 {
+                result.push(ConfigError::NoRule(id));
+            }
+            if ctl.rules > 1 {
+                result.push(ConfigError::DuplicateRule(id));
+            }
+            for dest in [&ctl.dest_low, &ctl.dest_high] {
+                if let Destination::Bot(target) = dest {
+                    if self.bots.get(target).map_or(0, |t| t.rules) == 0 {
+                        result.push(ConfigError::UnknownTarget(id, *target));
                     }
                 }
             }
+            if ctl.received > 2 {
+                result.push(ConfigError::TooManyChips(id, ctl.received));
+            }
+            if !fired.contains(&id) {
+                result.push(ConfigError::NeverFired(id));
+            }
         }
-        0
+        if result.is_empty() { Ok(()) } else { Err(result) }
     }
 
-    fn part_2(&self) -> u32 {
-        self.bins.get(&0).unwrap() * self.bins.get(&1).unwrap() * self.bins.get(&2).unwrap()
+    /// The product of the chips in output bins 0, 1 and 2, if all of them
+    /// got one.
+    fn part_2(&self) -> Option<u32> {
+        Some(self.bins.get(&0)? * self.bins.get(&1)? * self.bins.get(&2)?)
     }
 
 }
@@ -160,16 +365,14 @@ fn build_factory(cmds: &Vec<String>) -> Factory{
         match parts.next().unwrap() {
             "bot" => {
                 let bot: u32 = parts.next().unwrap().parse().unwrap();
-                let dest_low;
-                let dest_high;
-                match parts.nth(3).unwrap() {
-                    "bot" => { dest_low = Destination::Bot(parts.next().unwrap().parse().unwrap()) }
-                    "output" => { dest_low = Destination::Bin(parts.next().unwrap().parse().unwrap()) }
+                let dest_low = match parts.nth(3).unwrap() {
+                    "bot" => Destination::Bot(parts.next().unwrap().parse().unwrap()),
+                    "output" => Destination::Bin(parts.next().unwrap().parse().unwrap()),
                     _ => { continue; }
                 };
-                match parts.nth(3).unwrap() {
-                    "bot" => { dest_high = Destination::Bot(parts.next().unwrap().parse().unwrap()) }
-                    "output" => { dest_high = Destination::Bin(parts.next().unwrap().parse().unwrap()) }
+                let dest_high = match parts.nth(3).unwrap() {
+                    "bot" => Destination::Bot(parts.next().unwrap().parse().unwrap()),
+                    "output" => Destination::Bin(parts.next().unwrap().parse().unwrap()),
                     _ => { continue; }
                 };
                 factory.configure_bot(bot, dest_high, dest_low);
@@ -177,7 +380,7 @@ fn build_factory(cmds: &Vec<String>) -> Factory{
             "value" => {
                 let val: u32 = parts.next().unwrap().parse().unwrap();
                 let bot: u32 = parts.nth(3).unwrap().parse().unwrap();
-                factory.update_bot(bot, val);
+                factory.add_input(bot, val);
             }
             _ => { continue; }
         }
@@ -193,7 +396,194 @@ where P: AsRef<Path> {
 
 fn main() {
     let data = read_data("data/10/input.txt");
-    let factory = build_factory(&data);
-    println!("Part 1: {}", factory.part_1(17, 61));
-    println!("Part 2: {}", factory.part_2());
+    let mut factory = build_factory(&data);
+    let args: Vec<String> = std::env::args().collect();
+    let step_limit = match args.iter().position(|a| a == "--steps") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+            Some(limit) => limit,
+            None => {
+                eprintln!("Error: --steps needs a number");
+                std::process::exit(1);
+            }
+        },
+        None => 1_000_000,
+    };
+    let chip = match args.iter().position(|a| a == "--chip") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u32>().ok()) {
+            Some(chip) => Some(chip),
+            None => {
+                eprintln!("Error: --chip needs a chip value");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let run = factory.run(step_limit);
+    if let Some(i) = args.iter().position(|a| a == "--events") {
+        let path = args.get(i + 1).map(|p| p.as_str()).unwrap_or("events.jsonl");
+        if let Err(e) = factory.write_events(Path::new(path)) {
+            println!("Error writing {}: {}", path, e);
+        }
+    }
+    if let Some(i) = args.iter().position(|a| a == "--dot") {
+        let path = args.get(i + 1).map(|p| p.as_str()).unwrap_or("factory.dot");
+        if let Err(e) = std::fs::write(path, factory.to_dot(chip)) {
+            println!("Error writing {}: {}", path, e);
+        }
+    }
+    match run {
+        Err(e) => println!("Warning: {}", e),
+        Ok(_steps) => if let Err(errors) = factory.validate() {
+            for e in errors {
+                println!("Warning: {}", e);
+            }
+        },
+    }
+    match factory.part_1(17, 61) {
+        Some(bot) => println!("Part 1: {}", bot),
+        None => println!("Part 1: no bot compared 17 and 61"),
+    }
+    match factory.part_2() {
+        Some(product) => println!("Part 2: {}", product),
+        None => println!("Part 2: output bins 0, 1 and 2 are not all filled"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 6] = [
+        "value 5 goes to bot 2",
+        "bot 2 gives low to bot 1 and high to bot 0",
+        "value 3 goes to bot 1",
+        "bot 1 gives low to output 1 and high to bot 0",
+        "bot 0 gives low to output 2 and high to output 0",
+        "value 2 goes to bot 2",
+    ];
+
+    fn factory(lines: &[&str]) -> Factory {
+        build_factory(&lines.iter().map(|l| l.to_string()).collect())
+    }
+
+    fn example() -> Factory {
+        let mut factory = factory(&EXAMPLE);
+        factory.run(100).unwrap();
+        factory
+    }
+
+    #[test]
+    fn example_events() {
+        let factory = example();
+        let moves: Vec<&Event> = factory.events.iter().filter(|e| !matches!(e, Event::Input { .. })).collect();
+        assert_eq!(moves, [
+            &Event::Compare { bot: 2, low: 2, high: 5 },
+            &Event::Give { bot: 2, value: 5, to: Destination::Bot(0) },
+            &Event::Give { bot: 2, value: 2, to: Destination::Bot(1) },
+            &Event::Compare { bot: 1, low: 2, high: 3 },
+            &Event::Give { bot: 1, value: 3, to: Destination::Bot(0) },
+            &Event::Give { bot: 1, value: 2, to: Destination::Bin(1) },
+            &Event::Compare { bot: 0, low: 3, high: 5 },
+            &Event::Give { bot: 0, value: 5, to: Destination::Bin(0) },
+            &Event::Give { bot: 0, value: 3, to: Destination::Bin(2) },
+        ]);
+        assert_eq!(factory.part_1(5, 2), Some(2));
+        assert_eq!(factory.part_1(2, 5), Some(2));
+        assert_eq!(factory.part_1(99, 98), None);
+        assert_eq!(factory.part_2(), Some(30));
+    }
+
+    /// Runs the instructions and returns the problems found.
+    fn problems(lines: &[&str]) -> Result<(), Vec<ConfigError>> {
+        let mut factory = factory(lines);
+        factory.run(100).map_err(|e| vec![e])?;
+        factory.validate()
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(problems(&EXAMPLE), Ok(()));
+        let mut lines = EXAMPLE.to_vec();
+        lines.push("bot 2 gives low to bot 1 and high to bot 0");
+        assert_eq!(problems(&lines), Err(vec![ConfigError::DuplicateRule(2)]));
+        assert_eq!(problems(&["value 1 goes to bot 3"]),
+                   Err(vec![ConfigError::NoRule(3), ConfigError::NeverFired(3)]));
+        assert_eq!(problems(&["value 1 goes to bot 0", "value 2 goes to bot 0",
+                              "bot 0 gives low to bot 5 and high to output 0"]),
+                   Err(vec![ConfigError::UnknownTarget(0, 5), ConfigError::NoRule(5), ConfigError::NeverFired(5)]));
+        assert_eq!(problems(&["value 1 goes to bot 0", "value 2 goes to bot 0", "value 3 goes to bot 0",
+                              "bot 0 gives low to output 0 and high to output 1"]),
+                   Err(vec![ConfigError::TooManyChips(0, 3)]));
+        assert_eq!(problems(&["value 1 goes to bot 0", "bot 0 gives low to output 0 and high to output 1"]),
+                   Err(vec![ConfigError::NeverFired(0)]));
+        // two bots handing their chips back and forth forever
+        assert_eq!(problems(&["value 1 goes to bot 0", "value 2 goes to bot 0",
+                              "bot 0 gives low to bot 1 and high to bot 1",
+                              "bot 1 gives low to bot 0 and high to bot 0"]),
+                   Err(vec![ConfigError::StepLimit(100)]));
+    }
+
+    #[test]
+    fn run_in_order() {
+        let mut run = factory(&EXAMPLE);
+        assert_eq!(run.run(100), Ok(6));
+        // inputs in the order of the instructions, each ready bot fires
+        // before the next chip is taken
+        let steps: Vec<&Event> = run.events.iter().filter(|e| !matches!(e, Event::Give { .. })).collect();
+        assert_eq!(steps, [
+            &Event::Input { value: 5, bot: 2 },
+            &Event::Input { value: 3, bot: 1 },
+            &Event::Input { value: 2, bot: 2 },
+            &Event::Compare { bot: 2, low: 2, high: 5 },
+            &Event::Compare { bot: 1, low: 2, high: 3 },
+            &Event::Compare { bot: 0, low: 3, high: 5 },
+        ]);
+        let mut again = factory(&EXAMPLE);
+        again.run(100).unwrap();
+        assert_eq!(again.events, run.events);
+    }
+
+    #[test]
+    fn run_step_limit() {
+        let mut run = factory(&EXAMPLE);
+        assert_eq!(run.run(5), Err(ConfigError::StepLimit(5)));
+        assert_eq!(run.events.last(), Some(&Event::Give { bot: 1, value: 2, to: Destination::Bin(1) }));
+        // the limit counts from each call
+        assert_eq!(run.run(1), Ok(1));
+        let mut run = factory(&EXAMPLE);
+        assert_eq!(run.run(0), Err(ConfigError::StepLimit(0)));
+        assert_eq!(run.run(6), Ok(6));
+    }
+
+    #[test]
+    fn dot_graph() {
+        let dot = example().to_dot(Some(2));
+        let lines: Vec<&str> = dot.lines().map(|l| l.trim()).collect();
+        for id in 0..3 {
+            assert!(lines.contains(&format!("output{} [shape=box, label=\"output {}\"];", id, id).as_str()), "{}", dot);
+        }
+        // the way of chip 2 from its input through bots 2 and 1 to output 1
+        for edge in ["value2 -> bot2 [style=dashed, color=red, penwidth=2];",
+                     "bot2 -> bot1 [label=low, color=red, penwidth=2];",
+                     "bot1 -> output1 [label=low, color=red, penwidth=2];",
+                     "value5 -> bot2 [style=dashed];",
+                     "bot2 -> bot0 [label=high];",
+                     "bot1 -> bot0 [label=high];",
+                     "bot0 -> output2 [label=low];",
+                     "bot0 -> output0 [label=high];"] {
+            assert!(lines.contains(&edge), "{} missing in\n{}", edge, dot);
+        }
+        assert_eq!(lines.iter().filter(|l| l.contains("color=red")).count(), 3);
+        assert!(!example().to_dot(None).contains("color=red"));
+    }
+
+    #[test]
+    fn events_as_json() {
+        let factory = example();
+        assert_eq!(factory.events[0].to_json(), r#"{"event":"input","value":5,"bot":2}"#);
+        let json: Vec<String> = factory.events.iter().map(|e| e.to_json()).collect();
+        assert!(json.contains(&r#"{"event":"compare","bot":2,"low":2,"high":5}"#.to_string()));
+        assert!(json.contains(&r#"{"event":"give","bot":1,"value":2,"to":{"output":1}}"#.to_string()));
+        assert!(json.contains(&r#"{"event":"give","bot":2,"value":5,"to":{"bot":0}}"#.to_string()));
+    }
 }
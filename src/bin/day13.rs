@@ -0,0 +1,196 @@
+//! --- Day 13: A Maze of Twisty Little Cubicles ---
+//! 
+//! You arrive at the first floor of this new building to discover a much
+//! less welcoming environment than the shiny atrium of the last one.
+//! Instead, you are in a maze of twisty little cubicles, all alike.
+//! 
+//! Every location in this area is addressed by a pair of non-negative
+//! integers (x,y). Each such coordinate is either a wall or an open space.
+//! You can't move diagonally. The cube maze starts at 0,0 and seems to
+//! extend infinitely toward positive x and y; negative values are invalid,
+//! as they represent a location outside the building. You are in a small
+//! waiting area at 1,1.
+//! 
+//! While it seems chaotic, a nearby morale-boosting poster explains, the
+//! layout is actually quite logical. You can determine whether a given x,y
+//! coordinate will be a wall or an open space using a simple system:
+//! 
+//! - Find x*x + 3*x + 2*x*y + y + y*y.
+//! - Add the office designer's favorite number (your puzzle input).
+//! - Find the binary representation of that sum; count the number of bits
+//!   that are 1.
+//!   - If the number of bits that are 1 is even, it's an open space.
+//!   - If the number of bits that are 1 is odd, it's a wall.
+//! 
+//! For example, if the office designer's favorite number were 10, drawing
+//! walls as # and open spaces as ., the corner of the building containing
+//! 0,0 would look like this:
+//! 
+//!       0123456789
+//!     0 .#.####.##
+//!     1 ..#..#...#
+//!     2 #....##...
+//!     3 ###.#.###.
+//!     4 .##..#..#.
+//!     5 ..##....#.
+//!     6 #...##.###
+//! 
+//! Now, suppose you wanted to reach 7,4. The shortest route you could take
+//! is marked as O:
+//! 
+//!       0123456789
+//!     0 .#.####.##
+//!     1 .O#..#...#
+//!     2 #OOO.##...
+//!     3 ###O#.###.
+//!     4 .##OO#OO#.
+//!     5 ..##OOO.#.
+//!     6 #...##.###
+//! 
+//! Thus, reaching 7,4 would take a minimum of 11 steps (starting from your
+//! current location, 1,1).
+//! 
+//! What is the fewest number of steps required for you to reach 31,39?
+//! 
+//! --- Part Two ---
+//! 
+//! How many locations (distinct x,y coordinates, including your starting
+//! location) can you reach in at most 50 steps?
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aoc2016::grid::{render, Bfs, Point};
+
+
+const START: Point = Point { x: 1, y: 1 };
+
+/// The maze is endless, so the search for a target that is walled in has
+/// to give up somewhere.
+const MAX_STEPS: usize = 10_000;
+
+/// Whether the cubicle at `p` is a wall, outside the building counts as
+/// wall.
+fn is_wall(favourite: u32, p: Point) -> bool {
+    if p.x < 0 || p.y < 0 {
+        return true;
+    }
+    // (x + y)^2 + 3x + y + favourite stays below 2^64 for any point
+    let (x, y) = (p.x as u64, p.y as u64);
+    (x * x + 3 * x + 2 * x * y + y + y * y + favourite as u64).count_ones() % 2 == 1
+}
+
+/// The shortest path from the start to `target`, both included.
+fn shortest_path(favourite: u32, target: Point) -> Option<Vec<Point>> {
+    if is_wall(favourite, target) {
+        return None;
+    }
+    let mut search = Bfs::new(START, |p| !is_wall(favourite, p));
+    search.find(|&(p, distance)| p == target || distance > MAX_STEPS)
+        .filter(|&(p, _)| p == target)?;
+    search.path_to(target)
+}
+
+fn reachable(favourite: u32, steps: usize) -> usize {
+    Bfs::new(START, |p| !is_wall(favourite, p))
+        .take_while(|&(_, distance)| distance <= steps)
+        .count()
+}
+
+/// The maze with walls as `#`, open spaces as `.` and the path as `O`.
+fn draw_map(favourite: u32, path: &[Point]) -> String {
+    let width = path.iter().map(|p| p.x).max().unwrap_or(0) + 3;
+    let height = path.iter().map(|p| p.y).max().unwrap_or(0) + 3;
+    render(width, height, |p| {
+        if path.contains(&p) {
+            'O'
+        } else if is_wall(favourite, p) {
+            '#'
+        } else {
+            '.'
+        }
+    })
+}
+
+/// A point written as `x,y`.
+fn parse_point(txt: &str) -> Option<Point> {
+    let (x, y) = txt.split_once(',')?;
+    Some(Point::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+fn read_data<P>(name: P) -> io::Result<String>
+where P: AsRef<Path> {
+    Ok(fs::read_to_string(name)?.trim().to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let number = match args.iter().position(|a| a == "--number") {
+        Some(i) => args.get(i + 1).cloned().unwrap_or_default(),
+        None => match read_data("data/13/input.txt") {
+            Ok(number) => number,
+            Err(e) => {
+                eprintln!("Error reading data/13/input.txt: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let Ok(favourite) = number.parse::<u32>() else {
+        eprintln!("Error: invalid favourite number {:?}", number);
+        std::process::exit(1);
+    };
+    let target = match args.iter().position(|a| a == "--target") {
+        Some(i) => match args.get(i + 1).and_then(|t| parse_point(t)) {
+            Some(target) => target,
+            None => {
+                eprintln!("Error: --target needs a point like 31,39");
+                std::process::exit(1);
+            }
+        },
+        None => Point::new(31, 39),
+    };
+    match shortest_path(favourite, target) {
+        Some(path) => {
+            if args.iter().any(|a| a == "--map") {
+                print!("{}", draw_map(favourite, &path));
+            }
+            println!("Part 1: {}", path.len() - 1);
+        },
+        None => println!("Part 1: {},{} can't be reached", target.x, target.y),
+    }
+    println!("Part 2: {}", reachable(favourite, 50));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let path = shortest_path(10, Point::new(7, 4)).unwrap();
+        assert_eq!(path.len() - 1, 11);
+        assert_eq!(draw_map(10, &[]).lines().next(), Some(".#."));
+        assert_eq!(shortest_path(10, Point::new(1, 0)), None);
+    }
+
+    #[test]
+    fn far_points() {
+        let reference = |favourite: u32, x: i32, y: i32| {
+            let (x, y) = (x as u128, y as u128);
+            (x * x + 3 * x + 2 * x * y + y + y * y + favourite as u128).count_ones() % 2 == 1
+        };
+        for (favourite, x, y) in [(10, 50000, 3), (u32::MAX, 31, 39), (u32::MAX, i32::MAX, i32::MAX), (0, i32::MAX, 0)] {
+            assert_eq!(is_wall(favourite, Point::new(x, y)), reference(favourite, x, y), "{} {},{}", favourite, x, y);
+        }
+        assert_eq!(shortest_path(10, Point::new(50000, 3)), None);
+        assert_eq!(shortest_path(u32::MAX, Point::new(31, 39)), None);
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(parse_point("31,39"), Some(Point::new(31, 39)));
+        assert_eq!(parse_point("31"), None);
+        assert_eq!(parse_point("x,1"), None);
+    }
+}
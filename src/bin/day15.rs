@@ -0,0 +1,182 @@
+//! --- Day 15: Timing is Everything ---
+//! 
+//! The halls open into an interior plaza containing a large kinetic
+//! sculpture. The sculpture is in a sealed enclosure and seems to involve
+//! a set of identical spherical capsules that are carried to the top and
+//! allowed to bounce through the maze of spinning pieces.
+//! 
+//! Part of the sculpture is even interactive! When a button is pressed, a
+//! capsule is dropped and tries to fall through slots in a set of rotating
+//! discs to finally go through a little hole at the bottom and come out of
+//! the sculpture. If any of the slots aren't aligned with the capsule as it
+//! passes, the capsule bounces off the disc and soars away. You feel
+//! compelled to get one of those capsules.
+//! 
+//! The discs pause their motion each second and come in different sizes;
+//! they seem to each have a fixed number of positions at which they stop.
+//! You decide to call the position with the slot 0, and count up for each
+//! position it reaches next.
+//! 
+//! Furthermore, the discs are spaced out so that after you push the button,
+//! one second elapses before the first disc is reached, and one second
+//! elapses as the capsule passes from one disc to the one below it. So, if
+//! you push the button at time=100, then the capsule reaches the top disc at
+//! time=101, the second disc at time=102, the third disc at time=103, and
+//! so on.
+//! 
+//! The button will only drop a capsule at an integer time - no fractional
+//! seconds allowed.
+//! 
+//! For example, at time=0, suppose you see the following arrangement:
+//! 
+//!     Disc #1 has 5 positions; at time=0, it is at position 4.
+//!     Disc #2 has 2 positions; at time=0, it is at position 1.
+//! 
+//! If you press the button exactly at time=0, the capsule would start to
+//! fall; it would reach the first disc at time=1. Since the first disc was
+//! at position 4 at time=0, by time=1 it has ticked one position forward.
+//! As a five-position disc, the next position is 0, and the capsule falls
+//! through the slot.
+//! 
+//! Then, at time=2, the capsule reaches the second disc. The second disc
+//! has ticked forward two positions at this point: it started at position
+//! 1, then continued to position 0, and finally ended up at position 1
+//! again. Because there's only a slot at position 0, the capsule bounces
+//! away.
+//! 
+//! If, however, you wait until time=5 to push the button, then when the
+//! capsule reaches each disc, the first disc will have ticked forward 5+1 =
+//! 6 times (to position 0), and the second disc will have ticked forward
+//! 5+2 = 7 times (also to position 0). In this case, the capsule would fall
+//! through the discs and come out of the machine.
+//! 
+//! However, your situation has more than two discs; you've noted their
+//! positions in your puzzle input. What is the first time you can press the
+//! button to get a capsule?
+//! 
+//! --- Part Two ---
+//! 
+//! After getting the first capsule (it contained a star! what great
+//! fortune!), the machine detects your success and begins to rearrange
+//! itself.
+//! 
+//! When it's done, the discs are back in their original configuration as
+//! if it were time=0 again, but a new disc with 11 positions and starting
+//! at position 0 has appeared exactly one second below the
+//! previously-bottom disc.
+//! 
+//! With this new disc, and counting again starting from time=0 with the
+//! configuration in your puzzle input, what is the first time you can press
+//! the button to get another capsule?
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use aoc2016::math::{crt, CrtError};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Disc {
+    positions: u64,
+    start: u64,
+}
+
+/// Parses "Disc #1 has 5 positions; at time=0, it is at position 4."
+fn parse_disc(line: &str) -> Option<Disc> {
+    let words: Vec<&str> = line.trim_end_matches('.').split_whitespace().collect();
+    match words.as_slice() {
+        ["Disc", _, "has", positions, "positions;", "at", "time=0,", "it", "is", "at", "position", start] =>
+            Some(Disc { positions: positions.parse().ok()?, start: start.parse().ok()? }),
+        _ => None,
+    }
+}
+
+/// Parses the `positions,start` of an extra disc.
+fn parse_extra(txt: &str) -> Option<Disc> {
+    let (positions, start) = txt.split_once(',')?;
+    Some(Disc { positions: positions.trim().parse().ok()?, start: start.trim().parse().ok()? })
+}
+
+/// The first time to press the button, the capsule reaches disc `i`
+/// (counted from 1) at `t + i`, when it must be at position 0.
+fn first_time(discs: &[Disc]) -> Result<u128, CrtError> {
+    let congruences: Vec<(u64, u64)> = discs.iter().enumerate()
+        .map(|(i, d)| {
+            // a disc without positions is left to `crt` to report
+            let n = d.positions.max(1) as u128;
+            let offset = (d.start as u128 + i as u128 + 1) % n;
+            (((n - offset) % n) as u64, d.positions)
+        })
+        .collect();
+    crt(&congruences).map(|(t, _)| t)
+}
+
+fn read_data<P>(name: P) -> io::Result<Vec<String>>
+where P: AsRef<Path> {
+    let file = File::open(name)?;
+    BufReader::new(file).lines().collect()
+}
+
+fn main() {
+    let data = match read_data("data/15/input.txt") {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error reading data/15/input.txt: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut discs: Vec<Disc> = Vec::new();
+    for line in &data {
+        match parse_disc(line) {
+            Some(disc) => discs.push(disc),
+            None => println!("Skipping invalid line {:?}", line),
+        }
+    }
+    let args: Vec<String> = std::env::args().collect();
+    let extra = match args.iter().position(|a| a == "--extra-disc") {
+        Some(i) => match args.get(i + 1).and_then(|d| parse_extra(d)) {
+            Some(disc) => disc,
+            None => {
+                eprintln!("Error: --extra-disc needs positions and start like 11,0");
+                std::process::exit(1);
+            }
+        },
+        None => Disc { positions: 11, start: 0 },
+    };
+    match first_time(&discs) {
+        Ok(t) => println!("Part 1: {}", t),
+        Err(e) => println!("Part 1: {}", e),
+    }
+    discs.push(extra);
+    match first_time(&discs) {
+        Ok(t) => println!("Part 2: {}", t),
+        Err(e) => println!("Part 2: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let discs: Vec<Disc> = ["Disc #1 has 5 positions; at time=0, it is at position 4.",
+                                "Disc #2 has 2 positions; at time=0, it is at position 1."]
+            .iter().filter_map(|l| parse_disc(l)).collect();
+        assert_eq!(discs.len(), 2);
+        assert_eq!(first_time(&discs), Ok(5));
+        assert_eq!(parse_extra("11,0"), Some(Disc { positions: 11, start: 0 }));
+        assert_eq!(parse_extra("11"), None);
+    }
+
+    #[test]
+    fn odd_discs() {
+        let disc = |positions, start| Disc { positions, start };
+        assert_eq!(first_time(&[disc(5, 4), disc(0, 0)]), Err(CrtError::ZeroModulus(1)));
+        assert_eq!(first_time(&[disc(4, 0), disc(2, 0)]), Err(CrtError::NoSolution(1)));
+        assert_eq!(first_time(&[disc(u64::MAX, u64::MAX)]), Ok(u64::MAX as u128 - 1));
+        // u64::MAX is 1 modulo 7, both discs need t + 2 = 0
+        assert_eq!(first_time(&[disc(7, u64::MAX), disc(7, u64::MAX - 1)]), Ok(5));
+    }
+}
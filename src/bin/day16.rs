@@ -0,0 +1,207 @@
+//! --- Day 16: Dragon Checksum ---
+//! 
+//! You're done scanning this part of the network, but you've left traces of
+//! your presence. You need to overwrite some disks with random-looking data
+//! to cover your tracks and update the local security system with a new
+//! checksum for those disks.
+//! 
+//! For the data to not be suspicious, it needs to have certain properties;
+//! purely random data will be detected as tampering. To generate
+//! appropriate random data, you'll need to use a modified dragon curve.
+//! 
+//! Start with an appropriate initial state (your puzzle input). Then, so
+//! long as you don't have enough data yet to fill the disk, repeat the
+//! following steps:
+//! 
+//! - Call the data you have at this point "a".
+//! - Make a copy of "a"; call this copy "b".
+//! - Reverse the order of the characters in "b".
+//! - In "b", replace all instances of 0 with 1 and all 1s with 0.
+//! - The resulting data is "a", then a single 0, then "b".
+//! 
+//! For example, after a single step of this process,
+//! 
+//! - 1 becomes 100.
+//! - 0 becomes 001.
+//! - 11111 becomes 11111000000.
+//! - 111100001010 becomes 1111000010100101011110000.
+//! 
+//! Repeat these steps until you have enough data to fill the desired disk.
+//! 
+//! Once the data has been generated, you also need to create a checksum of
+//! that data. Calculate the checksum only for the data that fits on the
+//! disk, even if you generated more data than that in the previous step.
+//! 
+//! The checksum for some given data is created by considering each
+//! non-overlapping pair of characters in the input data. If the two
+//! characters match (00 or 11), the next checksum character is a 1. If the
+//! characters do not match (01 or 10), the next checksum character is a 0.
+//! This should produce a new string which is exactly half as long as the
+//! original. If the length of the checksum is even, repeat the process
+//! until you end up with a checksum with an odd length.
+//! 
+//! For example, suppose we want to fill a disk of length 12, and when we
+//! finally generate a string of at least length 12, the first 12 characters
+//! are 110010110100. To generate its checksum:
+//! 
+//! - Consider each pair: 11, 00, 10, 11, 01, 00.
+//! - These are same, same, different, same, different, same, producing
+//!   110101.
+//! - The resulting string has length 6, which is even, so we repeat the
+//!   process.
+//! - The pairs are 11 (same), 01 (different), 01 (different).
+//! - This produces the checksum 100, which has an odd length, so we stop.
+//! 
+//! Therefore, the checksum for this data is 100.
+//! 
+//! Combining all of these processes together, suppose you want to fill a
+//! disk of length 20 using an initial state of 10000: the data becomes
+//! 10000011110010000111, and its checksum is 01100.
+//! 
+//! The first disk you have to fill has length 272. Using the initial state
+//! in your puzzle input, what is the correct checksum?
+//! 
+//! --- Part Two ---
+//! 
+//! The second disk you have to fill has length 35651584. Again using the
+//! initial state in your puzzle input, what is the correct checksum for
+//! this disk?
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+
+/// The data never grows beyond the initial state: it is the initial state
+/// `a` and its reversed complement `b` taking turns, `a 0 b 0 a 1 b ...`,
+/// and the joining bits are the regular paperfolding sequence.
+struct Dragon {
+    initial: Vec<bool>,
+}
+
+impl Dragon {
+    fn new(initial: &[bool]) -> Dragon {
+        Dragon { initial: initial.to_vec() }
+    }
+
+    /// Bit number `pos` of the data.
+    fn bit(&self, pos: usize) -> bool {
+        let len = self.initial.len();
+        let (block, offset) = (pos / (len + 1), pos % (len + 1));
+        if offset == len {
+            // the joining bit after block k is bit 1 of k + 1 without its
+            // trailing zeros
+            let n = block + 1;
+            (n >> n.trailing_zeros() >> 1) & 1 == 1
+        } else if block.is_multiple_of(2) {
+            self.initial[offset]
+        } else {
+            !self.initial[len - 1 - offset]
+        }
+    }
+
+    /// The checksum of the first `len` bits. Folding a chunk of `2^k`
+    /// bits down to one gives 1 for an even number of ones, so each chunk
+    /// only needs its parity. `None` for an odd `len`, which can't be
+    /// folded.
+    fn checksum(&self, len: usize) -> Option<String> {
+        if !len.is_multiple_of(2) {
+            return None;
+        }
+        let chunk = 1 << len.trailing_zeros();
+        Some((0..len / chunk)
+            .map(|c| {
+                let ones = (c * chunk..(c + 1) * chunk).filter(|&p| self.bit(p)).count();
+                if ones.is_multiple_of(2) { '1' } else { '0' }
+            })
+            .collect())
+    }
+}
+
+/// The checksum following the puzzle step by step, for checking.
+#[cfg(test)]
+fn naive_checksum(initial: &[bool], len: usize) -> Option<String> {
+    if !len.is_multiple_of(2) {
+        return None;
+    }
+    let mut data = initial.to_vec();
+    while data.len() < len {
+        let b: Vec<bool> = data.iter().rev().map(|x| !x).collect();
+        data.push(false);
+        data.extend(b);
+    }
+    data.truncate(len);
+    while data.len().is_multiple_of(2) {
+        data = data.chunks(2).map(|p| p[0] == p[1]).collect();
+    }
+    Some(data.iter().map(|&b| if b { '1' } else { '0' }).collect())
+}
+
+fn parse_bits(txt: &str) -> Vec<bool> {
+    txt.trim().chars().map(|c| c == '1').collect()
+}
+
+fn read_data<P>(name: P) -> io::Result<Vec<bool>>
+where P: AsRef<Path> {
+    Ok(parse_bits(&fs::read_to_string(name)?))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let initial = match args.iter().position(|a| a == "--data") {
+        Some(i) => match args.get(i + 1) {
+            Some(data) => parse_bits(data),
+            None => {
+                eprintln!("Error: --data needs a value");
+                std::process::exit(1);
+            }
+        },
+        None => match read_data("data/16/input.txt") {
+            Ok(initial) => initial,
+            Err(e) => {
+                eprintln!("Error reading data/16/input.txt: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let dragon = Dragon::new(&initial);
+    println!("Part 1: {}", dragon.checksum(272).unwrap());
+    println!("Part 2: {}", dragon.checksum(35651584).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn example() {
+        let initial = parse_bits("10000");
+        assert_eq!(Dragon::new(&initial).checksum(20).as_deref(), Some("01100"));
+        assert_eq!(naive_checksum(&initial, 20).as_deref(), Some("01100"));
+        assert_eq!(Dragon::new(&initial).checksum(21), None);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rng = StdRng::seed_from_u64(2016);
+        let mut initials: Vec<Vec<bool>> = ["1", "0", "10000", "10111100110001111"].iter()
+            .map(|txt| parse_bits(txt))
+            .collect();
+        initials.extend((0..8).map(|_| (0..rng.gen_range(1..40)).map(|_| rng.gen()).collect()));
+        for initial in &initials {
+            let dragon = Dragon::new(initial);
+            for len in (2..=2000).step_by(2).chain([272]) {
+                assert_eq!(dragon.checksum(len), naive_checksum(initial, len), "{:?} length {}", initial, len);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_naive_on_second_disk() {
+        let initial = parse_bits("10111100110001111");
+        assert_eq!(Dragon::new(&initial).checksum(35651584), naive_checksum(&initial, 35651584));
+    }
+}
@@ -0,0 +1,221 @@
+//! --- Day 17: Two Steps Forward ---
+//! 
+//! You're trying to access a secure vault protected by a 4x4 grid of small
+//! rooms connected by doors. You start in the top-left room (marked S), and
+//! you can access the vault (marked V) once you reach the bottom-right
+//! room:
+//! 
+//!     #########
+//!     #S| | | #
+//!     #-#-#-#-#
+//!     # | | | #
+//!     #-#-#-#-#
+//!     # | | | #
+//!     #-#-#-#-#
+//!     # | | |  
+//!     ####### V
+//! 
+//! Fixed walls are marked with #, and doors are marked with - or |.
+//! 
+//! The doors in your current room are either open or closed (and locked)
+//! based on the hexadecimal MD5 hash of a passcode (your puzzle input)
+//! followed by a sequence of uppercase characters representing the path you
+//! have taken so far (U for up, D for down, L for left, and R for right).
+//! 
+//! Only the first four characters of the hash are used; they represent,
+//! respectively, the doors up, down, left, and right from your current
+//! position. Any b, c, d, e, or f means that the corresponding door is
+//! open; any other character (any number or a) means that the corresponding
+//! door is closed and locked.
+//! 
+//! To access the vault, all you need to do is reach the bottom-right room;
+//! reaching this room opens the vault and all doors in the maze.
+//! 
+//! For example, suppose the passcode is hijkl. Initially, you have taken no
+//! steps, and so your path is empty: you simply find the MD5 hash of hijkl
+//! alone. The first four characters of this hash are ced9, which indicate
+//! that up is open (c), down is open (e), left is open (d), and right is
+//! closed and locked (9). Because you start in the top-left corner, there
+//! are no "up" or "left" doors to be open, so your only choice is down.
+//! 
+//! Next, having gone only one step (down, or D), you find the hash of
+//! hijklD. This produces f2bc, which indicates that you can go back up,
+//! left (but that's a wall), or right. Going right means hashing hijklDR to
+//! get 5745 - all doors closed and locked. However, going up instead is
+//! worthwhile: even though it returns you to the room you started in, your
+//! path would then be DU, opening a different set of doors.
+//! 
+//! After going DU (and then hashing hijklDU to get 528e), only the right
+//! door is open; after going DUR, all doors lock. (Fortunately, your actual
+//! passcode is not hijkl).
+//! 
+//! Passcodes actually used by Easter Bunny Vault Security do allow access
+//! to the vault if you know the right path. For example:
+//! 
+//! - If your passcode were ihgpwlah, the shortest path would be DDRRRD.
+//! - With kglvqrro, the shortest path would be DDUDRLRRUDRD.
+//! - With ulqzkmiv, the shortest would be DRURDRUDDLLDLUURRDULRLDUUDDDRR.
+//! 
+//! Given your vault's passcode, what is the shortest path (the actual path,
+//! not just the length) to reach the vault?
+//! 
+//! --- Part Two ---
+//! 
+//! You're curious how robust this security solution really is, and so you
+//! decide to find longer and longer paths which still provide access to the
+//! vault. You remember that paths always end the first time they reach the
+//! bottom-right room (that is, they can never pass through it, only end in
+//! it).
+//! 
+//! For example:
+//! 
+//! - If your passcode were ihgpwlah, the longest path would take 370 steps.
+//! - With kglvqrro, the longest path would be 492 steps long.
+//! - With ulqzkmiv, the longest path would be 830 steps long.
+//! 
+//! What is the length of the longest path that reaches the vault?
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aoc2016::hashing::Seeded;
+
+
+const SIZE: i32 = 4;
+
+/// The doors in the order of the hash digits.
+const DOORS: [(u8, i32, i32); 4] = [(b'U', 0, -1), (b'D', 0, 1), (b'L', -1, 0), (b'R', 1, 0)];
+
+/// A room reached by a path from the top-left room.
+#[derive(Debug, Clone)]
+struct State {
+    path: Vec<u8>,
+    x: i32,
+    y: i32,
+}
+
+impl State {
+    fn start() -> State {
+        State { path: Vec::new(), x: 0, y: 0 }
+    }
+
+    fn is_vault(&self) -> bool {
+        self.x == SIZE - 1 && self.y == SIZE - 1
+    }
+
+    /// The rooms behind the open doors.
+    fn next(&self, vault: &Seeded) -> Vec<State> {
+        let hash = vault.hash(&self.path);
+        let digits = [hash[0] >> 4, hash[0] & 0xf, hash[1] >> 4, hash[1] & 0xf];
+        DOORS.iter().zip(digits)
+            .filter(|&(&(_, dx, dy), digit)| {
+                digit >= 0xb && (0..SIZE).contains(&(self.x + dx)) && (0..SIZE).contains(&(self.y + dy))
+            })
+            .map(|(&(door, dx, dy), _)| {
+                let mut path = self.path.clone();
+                path.push(door);
+                State { path, x: self.x + dx, y: self.y + dy }
+            })
+            .collect()
+    }
+}
+
+/// Every path to the vault, found depth first.
+struct Paths {
+    vault: Seeded,
+    stack: Vec<State>,
+}
+
+impl Paths {
+    fn new(passcode: &str) -> Paths {
+        Paths { vault: Seeded::new(passcode.as_bytes()), stack: vec![State::start()] }
+    }
+}
+
+impl Iterator for Paths {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some(state) = self.stack.pop() {
+            if state.is_vault() {
+                return Some(String::from_utf8(state.path).unwrap());
+            }
+            self.stack.extend(state.next(&self.vault));
+        }
+        None
+    }
+}
+
+/// The shortest path to the vault, found breadth first.
+fn shortest_path(passcode: &str) -> Option<String> {
+    let vault = Seeded::new(passcode.as_bytes());
+    let mut queue = VecDeque::from([State::start()]);
+    while let Some(state) = queue.pop_front() {
+        if state.is_vault() {
+            return Some(String::from_utf8(state.path).unwrap());
+        }
+        queue.extend(state.next(&vault));
+    }
+    None
+}
+
+fn longest_path(passcode: &str) -> Option<usize> {
+    Paths::new(passcode).map(|p| p.len()).max()
+}
+
+fn read_data<P>(name: P) -> io::Result<String>
+where P: AsRef<Path> {
+    Ok(fs::read_to_string(name)?.trim().to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let passcode = match args.iter().position(|a| a == "--passcode") {
+        Some(i) => match args.get(i + 1) {
+            Some(passcode) => passcode.clone(),
+            None => {
+                eprintln!("Error: --passcode needs a value");
+                std::process::exit(1);
+            }
+        },
+        None => match read_data("data/17/input.txt") {
+            Ok(passcode) => passcode,
+            Err(e) => {
+                eprintln!("Error reading data/17/input.txt: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    if args.iter().any(|a| a == "--paths") {
+        for path in Paths::new(&passcode) {
+            println!("{}", path);
+        }
+    }
+    match shortest_path(&passcode) {
+        Some(path) => println!("Part 1: {}", path),
+        None => println!("Part 1: the vault can't be reached"),
+    }
+    match longest_path(&passcode) {
+        Some(len) => println!("Part 2: {}", len),
+        None => println!("Part 2: the vault can't be reached"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples() {
+        assert_eq!(shortest_path("hijkl"), None);
+        assert_eq!(longest_path("hijkl"), None);
+        assert_eq!(shortest_path("ihgpwlah").as_deref(), Some("DDRRRD"));
+        assert_eq!(longest_path("ihgpwlah"), Some(370));
+        assert_eq!(shortest_path("kglvqrro").as_deref(), Some("DDUDRLRRUDRD"));
+        assert_eq!(longest_path("kglvqrro"), Some(492));
+        assert_eq!(shortest_path("ulqzkmiv").as_deref(), Some("DRURDRUDDLLDLUURRDULRLDUUDDDRR"));
+        assert_eq!(longest_path("ulqzkmiv"), Some(830));
+    }
+}
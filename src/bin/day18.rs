@@ -0,0 +1,208 @@
+//! --- Day 18: Like a Rogue ---
+//! 
+//! As you enter this room, you hear a loud click! Some of the tiles in the
+//! floor here seem to be pressure plates for traps, and the trap you just
+//! triggered has run out of... whatever it tried to do to you. You doubt
+//! you'll be so lucky next time.
+//! 
+//! Upon closer examination, the traps and safe tiles in this room seem to
+//! follow a pattern. The tiles are arranged into rows that are all the same
+//! width; you take note of the safe tiles (.) and traps (^) in the first
+//! row (your puzzle input).
+//! 
+//! The type of tile (trapped or safe) in each row is based on the types of
+//! the tiles in the same position, and to either side of that position, in
+//! the previous row. (If either side is off either end of the row, it
+//! counts as "safe" because there isn't a trap embedded in the wall.)
+//! 
+//! For example, the new tile in the second row, at position 4, is based on
+//! the tiles in the first row at positions 3, 4 and 5; call these the
+//! left, center, and right tiles. Then, a new tile is a trap only in one of
+//! the following situations:
+//! 
+//! - Its left and center tiles are traps, but its right tile is not.
+//! - Its center and right tiles are traps, but its left tile is not.
+//! - Only its left tile is a trap.
+//! - Only its right tile is a trap.
+//! 
+//! In any other situation, the new tile is safe.
+//! 
+//! Then, starting with the row ..^^., you can determine the next row by
+//! applying those rules to each new tile, giving .^^^^, and then ^^..^.
+//! 
+//! Here's a larger example with ten tiles per row and ten rows:
+//! 
+//!     .^^.^.^^^^
+//!     ^^^...^..^
+//!     ^.^^.^.^^.
+//!     ..^^...^^^
+//!     .^^^^.^^.^
+//!     ^^..^.^^..
+//!     ^^^^..^^^.
+//!     ^..^^^^.^^
+//!     .^^^..^.^^
+//!     ^^.^^^..^^
+//! 
+//! In ten rows, this larger example has 38 safe tiles.
+//! 
+//! Starting with the map in your puzzle input, in a total of 40 rows
+//! (including the starting row), how many safe tiles are there?
+//! 
+//! --- Part Two ---
+//! 
+//! How many safe tiles are there in a total of 400000 rows?
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aoc2016::grid::render_bits;
+
+
+/// A row of tiles packed into bits, 1 is a trap.
+///
+/// The four trap rules all say that the left and the right tile differ,
+/// whatever the center, so the next row is the row shifted left XOR the
+/// row shifted right.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl Row {
+    fn parse(txt: &str) -> Row {
+        let width = txt.len();
+        let mut words = vec![0; width.div_ceil(64)];
+        for (i, c) in txt.bytes().enumerate() {
+            if c == b'^' {
+                words[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Row { width, words }
+    }
+
+    fn is_trap(&self, i: usize) -> bool {
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn safe_tiles(&self) -> usize {
+        self.width - self.words.iter().map(|w| w.count_ones() as usize).sum::<usize>()
+    }
+
+    /// Writes the following row into `next`.
+    fn next_into(&self, next: &mut Row) {
+        let n = self.words.len();
+        for i in 0..n {
+            // bit j of `left` is tile j - 1, bit j of `right` is tile j + 1
+            let carry_left = if i > 0 { self.words[i - 1] >> 63 } else { 0 };
+            let carry_right = if i + 1 < n { self.words[i + 1] << 63 } else { 0 };
+            let left = self.words[i] << 1 | carry_left;
+            let right = self.words[i] >> 1 | carry_right;
+            next.words[i] = left ^ right;
+        }
+        // the bit after the last tile is a wall
+        if !self.width.is_multiple_of(64) {
+            next.words[n - 1] &= (1 << (self.width % 64)) - 1;
+        }
+    }
+}
+
+/// Counts the safe tiles in `rows` rows, alternating between two buffers.
+fn count_safe(first: &Row, rows: usize) -> usize {
+    let (mut row, mut next) = (first.clone(), first.clone());
+    let mut safe = 0;
+    for _ in 0..rows {
+        safe += row.safe_tiles();
+        row.next_into(&mut next);
+        std::mem::swap(&mut row, &mut next);
+    }
+    safe
+}
+
+/// The first `rows` rows with traps as `^` and safe tiles as `.`.
+fn draw_rows(first: &Row, rows: usize) -> String {
+    let mut all = vec![first.clone()];
+    while all.len() < rows {
+        let mut next = first.clone();
+        all[all.len() - 1].next_into(&mut next);
+        all.push(next);
+    }
+    render_bits(first.width, rows, '^', '.', |x, y| all[y].is_trap(x))
+}
+
+fn read_data<P>(name: P) -> io::Result<String>
+where P: AsRef<Path> {
+    Ok(fs::read_to_string(name)?.trim().to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let data = match args.iter().position(|a| a == "--data") {
+        Some(i) => match args.get(i + 1) {
+            Some(data) => data.clone(),
+            None => {
+                eprintln!("Error: --data needs a value");
+                std::process::exit(1);
+            }
+        },
+        None => match read_data("data/18/input.txt") {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error reading data/18/input.txt: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let first = Row::parse(&data);
+    if let Some(i) = args.iter().position(|a| a == "--map") {
+        let rows = match args.get(i + 1).filter(|v| !v.starts_with("--")) {
+            Some(v) => match v.parse() {
+                Ok(rows) => rows,
+                Err(_) => {
+                    eprintln!("Error: invalid number of rows {:?}", v);
+                    std::process::exit(1);
+                }
+            },
+            None => 40,
+        };
+        print!("{}", draw_rows(&first, rows));
+    }
+    println!("Part 1: {}", count_safe(&first, 40));
+    println!("Part 2: {}", count_safe(&first, 400000));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The next row by the four rules, tile by tile.
+    fn naive_next(row: &str) -> String {
+        let tiles: Vec<bool> = row.bytes().map(|c| c == b'^').collect();
+        (0..tiles.len())
+            .map(|i| {
+                let left = i > 0 && tiles[i - 1];
+                let right = i + 1 < tiles.len() && tiles[i + 1];
+                if left != right { '^' } else { '.' }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(draw_rows(&Row::parse("..^^."), 3), "..^^.\n.^^^^\n^^..^\n");
+        assert_eq!(count_safe(&Row::parse(".^^.^.^^^^"), 10), 38);
+    }
+
+    #[test]
+    fn wide_rows() {
+        let mut txt: String = (0..150).map(|i| if i * i % 7 < 3 { '^' } else { '.' }).collect();
+        for _ in 0..20 {
+            let next = naive_next(&txt);
+            let mut row = Row::parse(&txt);
+            Row::parse(&txt).next_into(&mut row);
+            assert_eq!(row, Row::parse(&next));
+            txt = next;
+        }
+    }
+}
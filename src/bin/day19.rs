@@ -0,0 +1,180 @@
+//! --- Day 19: An Elephant Named Joseph ---
+//! 
+//! The Elves contact you over a highly secure emergency channel. Back at
+//! the North Pole, the Elves are busy misunderstanding White Elephant
+//! parties.
+//! 
+//! Each Elf brings a present. They all sit in a circle, numbered starting
+//! with position 1. Then, starting with the first Elf, they take turns
+//! stealing all the presents from the Elf to their left. An Elf with no
+//! presents is removed from the circle and does not take turns.
+//! 
+//! For example, with five Elves (numbered 1 to 5):
+//! 
+//!       1
+//!     5   2
+//!      4 3
+//! 
+//! - Elf 1 takes Elf 2's present.
+//! - Elf 2 has no presents and is skipped.
+//! - Elf 3 takes Elf 4's present.
+//! - Elf 4 has no presents and is also skipped.
+//! - Elf 5 takes Elf 1's two presents.
+//! - Neither Elf 1 nor Elf 2 have any presents, so both are skipped.
+//! - Elf 3 takes Elf 5's three presents.
+//! 
+//! So, with five Elves, the Elf that sits starting in position 3 gets all
+//! the presents.
+//! 
+//! With the number of Elves given in your puzzle input, which Elf gets all
+//! the presents?
+//! 
+//! --- Part Two ---
+//! 
+//! Realizing the folly of their present-exchange rules, the Elves agree to
+//! instead steal presents from the Elf directly across the circle. If two
+//! Elves are across the circle, the one on the left (from the perspective
+//! of the stealer) is stolen from. The other rules remain unchanged: Elves
+//! with no presents are removed from the circle entirely, and the other
+//! elves move in slightly to keep the circle evenly spaced.
+//! 
+//! For example, with five Elves (again numbered 1 to 5):
+//! 
+//! - The Elves sit in a circle; Elf 1 goes first. Elf 1 steals from Elf 3.
+//! - Elf 2 is next; Elf 2 steals from Elf 5.
+//! - Elf 4 is next; Elf 4 steals from Elf 1.
+//! - Elf 2 is next; Elf 2 steals from Elf 4.
+//! - Elf 2 is the last one left, so Elf 2 gets all the presents.
+//! 
+//! So, with five Elves, the Elf that sits starting in position 2 gets all
+//! the presents.
+//! 
+//! With the number of Elves given in your puzzle input, which Elf now gets
+//! all the presents?
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule {
+    /// steal from the elf to the left
+    Left,
+    /// steal from the elf across the circle
+    Across,
+}
+
+/// Plays the game with `n` elves, every turn takes constant time.
+///
+/// The circle is split into two halves, the elf whose turn it is at the
+/// front of `left`. The elf to the left is the next one in the circle, the
+/// elf across is the first one of `right`, or the last of `left` while that
+/// is the longer half. After a turn the elf moves from the front of `left`
+/// to the back of `right`, and the halves are balanced again.
+fn simulate(n: usize, rule: Rule) -> usize {
+    let mut left: VecDeque<usize> = (1..=n.div_ceil(2)).collect();
+    let mut right: VecDeque<usize> = (n.div_ceil(2) + 1..=n).collect();
+    while left.len() + right.len() > 1 {
+        match rule {
+            Rule::Left => if left.len() > 1 {
+                left.remove(1);
+            } else {
+                right.pop_front();
+            },
+            Rule::Across => if left.len() > right.len() {
+                left.pop_back();
+            } else {
+                right.pop_front();
+            },
+        }
+        right.push_back(left.pop_front().unwrap());
+        while right.len() > left.len() {
+            left.push_back(right.pop_front().unwrap());
+        }
+    }
+    left.pop_front().or_else(|| right.pop_front()).unwrap()
+}
+
+/// The winner with `n` elves, found from the pattern of the simulation:
+/// stealing to the left is the Josephus problem, `2 l + 1` for
+/// `n = 2^m + l`; stealing across counts up by one from the last power of
+/// 3 and then by two, until the next power of 3 wins.
+fn winner(n: u64, rule: Rule) -> u64 {
+    match rule {
+        Rule::Left => {
+            let power = 1 << (63 - n.leading_zeros());
+            2 * (n - power) + 1
+        },
+        Rule::Across => {
+            let mut power = 1;
+            while power * 3 <= n {
+                power *= 3;
+            }
+            if n == power {
+                n
+            } else if n <= 2 * power {
+                n - power
+            } else {
+                2 * n - 3 * power
+            }
+        },
+    }
+}
+
+fn read_data<P>(name: P) -> io::Result<String>
+where P: AsRef<Path> {
+    Ok(fs::read_to_string(name)?.trim().to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let number = match args.iter().position(|a| a == "--elves") {
+        Some(i) => args.get(i + 1).cloned().unwrap_or_default(),
+        None => match read_data("data/19/input.txt") {
+            Ok(number) => number,
+            Err(e) => {
+                eprintln!("Error reading data/19/input.txt: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let Ok(elves) = number.parse::<u64>() else {
+        eprintln!("Error: invalid number of elves {:?}", number);
+        std::process::exit(1);
+    };
+    if elves == 0 {
+        println!("There are no elves to play");
+        return;
+    }
+    if args.iter().any(|a| a == "--simulate") {
+        println!("Part 1: {}", simulate(elves as usize, Rule::Left));
+        println!("Part 2: {}", simulate(elves as usize, Rule::Across));
+    } else {
+        println!("Part 1: {}", winner(elves, Rule::Left));
+        println!("Part 2: {}", winner(elves, Rule::Across));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        assert_eq!(simulate(5, Rule::Left), 3);
+        assert_eq!(simulate(5, Rule::Across), 2);
+        assert_eq!(winner(5, Rule::Left), 3);
+        assert_eq!(winner(5, Rule::Across), 2);
+    }
+
+    #[test]
+    fn closed_form_matches_simulation() {
+        for rule in [Rule::Left, Rule::Across] {
+            for n in 1..=10_000 {
+                assert_eq!(winner(n as u64, rule), simulate(n, rule) as u64, "{:?} with {} elves", rule, n);
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! --- Day 23: Safe Cracking ---
+//! 
+//! This is one of the top floors of the nicest tower in EBHQ. The Easter
+//! Bunny's private office is here, complete with a safe hidden behind a
+//! painting, and who wouldn't hide a star in a safe behind a painting?
+//! 
+//! The safe has a digital screen and keypad for code entry. A sticky note
+//! attached to the safe has a password hint on it: "eggs". The painting is
+//! of a large rabbit coloring some eggs. You see 7.
+//! 
+//! When you go to type the code, though, nothing appears on the display;
+//! instead, the keypad comes apart in your hands, apparently having been
+//! smashed. Behind it is some kind of socket - one that matches a connector
+//! in your prototype computer! You pull apart the smashed keypad and extract
+//! the logic circuit, plug it into your computer, and plug your computer
+//! into the safe.
+//! 
+//! Now, you just need to figure out what output the keypad would have sent
+//! to the safe. You extract the assembunny code from the logic chip (your
+//! puzzle input).
+//! 
+//! The code looks like it uses almost the same architecture and instruction
+//! set that the monorail computer used! You should be able to use the same
+//! assembunny interpreter for this as you did there, but with one new
+//! instruction:
+//! 
+//! tgl x toggles the instruction x away (pointing at instructions like jnz
+//! does: positive means forward; negative means backward):
+//! 
+//! - For one-argument instructions, inc becomes dec, and all other
+//!   one-argument instructions become inc.
+//! - For two-argument instructions, jnz becomes cpy, and all other
+//!   two-instructions become jnz.
+//! - The arguments of a toggled instruction are not affected.
+//! - If an attempt is made to toggle an instruction outside the program,
+//!   nothing happens.
+//! - If toggling produces an invalid instruction (like cpy 1 2) and an
+//!   attempt is later made to execute that instruction, skip it instead.
+//! - If tgl toggles itself (for example, if a is 0, tgl a would target
+//!   itself and become inc a), the resulting instruction is not executed
+//!   until the next time it is reached.
+//! 
+//! For example, given this program:
+//! 
+//!     cpy 2 a
+//!     tgl a
+//!     tgl a
+//!     tgl a
+//!     cpy 1 a
+//!     dec a
+//!     dec a
+//! 
+//! In this example, the final value in register a is 3.
+//! 
+//! The rest of the electronics seem to place the keypad entry (the number
+//! of eggs, 7) in register a, run the code, and then send the value left in
+//! register a to the safe.
+//! 
+//! What value should be sent to the safe?
+//! 
+//! --- Part Two ---
+//! 
+//! The safe doesn't open, but it does make several angry noises to express
+//! its frustration.
+//! 
+//! You're quite sure your logic is working correctly, so the only other
+//! thing is... you check the painting again. As it turns out, colored eggs
+//! are still eggs. Now you count 12.
+//! 
+//! As you run the program with this new input, the prototype computer
+//! begins to overheat. You wonder what's taking so long, and whether the
+//! lack of any instruction more powerful than "add one" has anything to do
+//! with it. Don't bunnies usually multiply?
+//! 
+//! Anyway, what value should actually be sent to the safe?
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use aoc2016::assembunny::{parse_program, Instruction, Vm};
+
+
+/// Runs the program with `eggs` in register a, returns the machine after
+/// it halted.
+fn execute(program: &[Instruction], eggs: i64, optimised: bool) -> Vm {
+    let mut vm = if optimised {
+        Vm::optimised(program.to_vec())
+    } else {
+        Vm::new(program.to_vec())
+    };
+    vm.registers[0] = eggs;
+    vm.run();
+    vm
+}
+
+fn read_data<P>(name: P) -> io::Result<Vec<String>>
+where P: AsRef<Path> {
+    let file = File::open(name)?;
+    BufReader::new(file).lines().collect()
+}
+
+fn main() {
+    let data = match read_data("data/23/input.txt") {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error reading data/23/input.txt: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let program = match parse_program(&data) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Error: invalid program: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let args: Vec<String> = std::env::args().collect();
+    let optimised = !args.iter().any(|a| a == "--naive");
+    let listing = args.iter().any(|a| a == "--listing");
+    if listing {
+        println!("Optimised program:");
+        print!("{}", Vm::optimised(program.clone()).listing());
+    }
+    println!("Part 1: {}", execute(&program, 7, optimised).registers[0]);
+    let vm = execute(&program, 12, optimised);
+    if listing {
+        println!("Optimised program after the toggles:");
+        print!("{}", Vm::optimised(vm.program.clone()).listing());
+    }
+    println!("Part 2: {}", vm.registers[0]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let program = parse_program(&["cpy 2 a", "tgl a", "tgl a", "tgl a", "cpy 1 a", "dec a", "dec a"]).unwrap();
+        assert_eq!(execute(&program, 0, false).registers[0], 3);
+        assert_eq!(execute(&program, 0, true).registers[0], 3);
+    }
+}
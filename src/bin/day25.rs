@@ -0,0 +1,252 @@
+//! --- Day 25: Clock Signal ---
+//! 
+//! You open the door and find yourself on the roof. The city sprawls away
+//! from you for miles and miles.
+//! 
+//! There's not much time now - it's already Christmas, but you're nowhere
+//! near the North Pole, much too far to deliver these stars to the sleigh
+//! in time.
+//! 
+//! However, maybe the huge antenna up here can offer a solution. After all,
+//! the sleigh doesn't need the stars, exactly; it needs the timing data
+//! they provide, and you happen to have a massive signal generator right
+//! here.
+//! 
+//! You connect the stars you have to your prototype computer, connect that
+//! to the antenna, and begin the transmission.
+//! 
+//! Nothing happens.
+//! 
+//! You call the service number printed on the side of the antenna and
+//! quickly explain the situation. "I'm not sure what kind of equipment you
+//! have connected over there," he says, "but you need a clock signal." You
+//! try to explain that this is a signal for a clock.
+//! 
+//! "No, no, a clock signal - timing information so the antenna computer
+//! knows how to read the data you're sending it. An endless, alternating
+//! pattern of 0, 1, 0, 1, 0, 1, 0, 1, 0, 1...." He trails off.
+//! 
+//! You ask if the antenna can handle a clock signal at the frequency you
+//! would need to use for the data from the stars. "There's no way it can!
+//! The only antenna we've installed capable of that is on top of a top-
+//! secret Easter Bunny installation, and you're definitely not-" You hang
+//! up the phone.
+//! 
+//! You've extracted the antenna's clock signal generation assembunny code
+//! (your puzzle input); it looks mostly compatible with code you worked on
+//! just recently.
+//! 
+//! This antenna code, being a signal generator, uses one extra instruction:
+//! 
+//!     out x transmits x (either an integer or the value of a register) as
+//!           the next value for the clock signal.
+//! 
+//! The code takes a value (via register a) that describes the signal to
+//! generate, but you're not sure how it's used. You'll have to find the
+//! input to produce the right signal through experimentation.
+//! 
+//! What is the lowest positive integer that can be used to initialize
+//! register a and cause the code to output a clock signal of 0, 1, 0, 1...
+//! repeating forever?
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use aoc2016::assembunny::{optimise, parse_program, Fused, Instruction, Operand, Step, Vm};
+
+
+/// How many steps may pass between two values of the signal.
+const STEP_LIMIT: usize = 10_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Signal {
+    /// the output repeats 0, 1, 0, 1... forever
+    Clock,
+    /// the output stopped, or the value with this index was wrong
+    Broken(usize),
+    /// no output within `STEP_LIMIT` steps
+    Undecided(usize),
+}
+
+/// Runs the program with `a` in register a until it either sends a wrong
+/// value or gets back to the state it had after an earlier value, then it
+/// will repeat the same values forever.
+fn check_signal(program: &[Instruction], a: i64) -> Signal {
+    let mut vm = Vm::optimised(program.to_vec());
+    vm.registers[0] = a;
+    let mut seen: HashSet<(Vm, i64)> = HashSet::new();
+    let mut count = 0;
+    loop {
+        let mut steps = 0;
+        let value = loop {
+            if steps == STEP_LIMIT {
+                return Signal::Undecided(count);
+            }
+            steps += 1;
+            match vm.step() {
+                Step::Continue => (),
+                Step::Output(value) => break value,
+                Step::Halted => return Signal::Broken(count),
+            }
+        };
+        let expected = count as i64 % 2;
+        if value != expected {
+            return Signal::Broken(count);
+        }
+        count += 1;
+        // same machine, expecting the same next value
+        if !seen.insert((vm.clone(), 1 - expected)) {
+            return Signal::Clock;
+        }
+    }
+}
+
+/// The programs seen so far start by adding a product of two constants to
+/// `a` and then send the bits of the sum, lowest first, over and over. The
+/// signal is a clock if the sum is 0b10, 0b1010, 0b101010...
+///
+/// Returns the constant, or `None` if the program looks different or the
+/// product doesn't fit.
+fn reverse_engineer(program: &[Instruction]) -> Option<i64> {
+    use Instruction::Cpy;
+    use Operand::{Reg, Value};
+    let fused = optimise(program);
+    match (program, fused.get(2)) {
+        ([Cpy(Reg(0), Reg(sum)), Cpy(Value(outer), Reg(c)), ..],
+         Some(Some(Fused::Mul { dst, src: Value(inner), outer: o, .. })))
+            if *sum == *dst && *c == *o => outer.checked_mul(*inner),
+        _ => None,
+    }
+}
+
+/// Whether the reverse engineered program sends a clock signal for `a`.
+fn is_clock_fast(offset: i64, a: i64) -> bool {
+    let mut n = 2;
+    while n < offset + a {
+        n = 4 * n + 2;
+    }
+    n == offset + a
+}
+
+/// The lowest `a` for the reverse engineered program.
+fn lowest_fast(offset: i64) -> i64 {
+    (1..).find(|&a| is_clock_fast(offset, a)).unwrap()
+}
+
+/// The largest `a` tried before giving up on the program.
+const MAX_A: i64 = 100_000;
+
+/// Why no lowest `a` was found.
+#[derive(Debug, PartialEq)]
+enum SearchError {
+    /// no `a` up to this one gives a clock signal
+    NoClock(i64),
+    /// with this `a` the program sent the given number of values and then
+    /// nothing for `STEP_LIMIT` steps
+    Undecided(i64, usize),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchError::NoClock(max) => write!(f, "no clock signal for a = 1 to {}", max),
+            SearchError::Undecided(a, count) =>
+                write!(f, "a = {} sent no value within {} steps after {} values", a, STEP_LIMIT, count),
+        }
+    }
+}
+
+/// The lowest `a` up to `max_a` that makes the program send a clock signal.
+fn lowest_clock(program: &[Instruction], max_a: i64) -> Result<i64, SearchError> {
+    for a in 1..=max_a {
+        match check_signal(program, a) {
+            Signal::Clock => return Ok(a),
+            Signal::Broken(_) => (),
+            Signal::Undecided(count) => return Err(SearchError::Undecided(a, count)),
+        }
+    }
+    Err(SearchError::NoClock(max_a))
+}
+
+fn read_data<P>(name: P) -> io::Result<Vec<String>>
+where P: AsRef<Path> {
+    let file = File::open(name)?;
+    BufReader::new(file).lines().collect()
+}
+
+fn main() {
+    let data = match read_data("data/25/input.txt") {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error reading data/25/input.txt: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let program = match parse_program(&data) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Error: invalid program: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let a = match lowest_clock(&program, MAX_A) {
+        Ok(a) => a,
+        Err(e) => {
+            println!("Part 1: {}", e);
+            return;
+        }
+    };
+    match reverse_engineer(&program) {
+        Some(offset) if lowest_fast(offset) == a => println!("Fast path finds the same lowest a = {}", a),
+        Some(offset) => println!("Fast path disagrees, it finds a = {}", lowest_fast(offset)),
+        None => println!("Fast path: unknown kind of program"),
+    }
+    println!("Part 1: {}", a);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK: [&str; 30] = [
+        "cpy a d", "cpy 4 c", "cpy 633 b", "inc d", "dec b", "jnz b -2", "dec c", "jnz c -5", "cpy d a",
+        "jnz 0 0", "cpy a b", "cpy 0 a", "cpy 2 c", "jnz b 2", "jnz 1 6", "dec b", "dec c", "jnz c -4",
+        "inc a", "jnz 1 -7", "cpy 2 b", "jnz c 2", "jnz 1 4", "dec b", "dec c", "jnz 1 -4", "jnz 0 0",
+        "out b", "jnz a -19", "jnz 1 -21",
+    ];
+
+    #[test]
+    fn clock() {
+        let program = parse_program(&CLOCK).unwrap();
+        assert_eq!(lowest_clock(&program, MAX_A), Ok(198));
+        assert_eq!(reverse_engineer(&program), Some(4 * 633));
+        assert_eq!(lowest_fast(4 * 633), 198);
+    }
+
+    #[test]
+    fn huge_constants() {
+        let mut lines = CLOCK.to_vec();
+        lines[1] = "cpy 4000000000 c";
+        lines[2] = "cpy 4000000000 b";
+        assert_eq!(reverse_engineer(&parse_program(&lines).unwrap()), None);
+        lines[1] = "cpy 4000 c";
+        assert_eq!(reverse_engineer(&parse_program(&lines).unwrap()), Some(4000 * 4000000000));
+    }
+
+    #[test]
+    fn no_clock() {
+        let program = parse_program(&["out 0", "jnz 1 -1"]).unwrap();
+        assert_eq!(lowest_clock(&program, 10), Err(SearchError::NoClock(10)));
+        let program = parse_program(&["out 0", "out 1"]).unwrap();
+        assert_eq!(lowest_clock(&program, 10), Err(SearchError::NoClock(10)));
+    }
+
+    #[test]
+    fn silent_program() {
+        let program = parse_program(&["out 0", "jnz 1 0"]).unwrap();
+        assert_eq!(lowest_clock(&program, 10), Err(SearchError::Undecided(1, 1)));
+    }
+}
@@ -0,0 +1,130 @@
+//! Points and directions on an unbounded 2D grid, breadth first search and
+//! ASCII maps, as used by days 1, 8, 13 and 18.
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Add;
+
+/// A position, `y` grows downwards on maps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    /// The taxicab distance from the origin.
+    pub fn manhattan(&self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+
+    /// The points one step up, right, down and left.
+    pub fn neighbours(&self) -> [Point; 4] {
+        Direction::ALL.map(|d| *self + d.delta())
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self as usize + 3) % 4]
+    }
+
+    /// The step in this direction, north is up.
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::East => Point::new(1, 0),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+        }
+    }
+}
+
+/// Breadth first search from a start point through the points accepted by
+/// `open`, iterating over the points reached and their distances in order.
+pub struct Bfs<F: Fn(Point) -> bool> {
+    open: F,
+    queue: VecDeque<(Point, usize)>,
+    /// the point every reached point was first reached from
+    parents: HashMap<Point, Point>,
+}
+
+impl<F: Fn(Point) -> bool> Bfs<F> {
+    pub fn new(start: Point, open: F) -> Bfs<F> {
+        Bfs { open, queue: VecDeque::from([(start, 0)]), parents: HashMap::from([(start, start)]) }
+    }
+
+    /// The path from the start to `target` including both, if it was
+    /// reached already.
+    pub fn path_to(&self, target: Point) -> Option<Vec<Point>> {
+        let mut path = vec![target];
+        let mut current = target;
+        loop {
+            let parent = *self.parents.get(&current)?;
+            if parent == current {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+impl<F: Fn(Point) -> bool> Iterator for Bfs<F> {
+    type Item = (Point, usize);
+
+    fn next(&mut self) -> Option<(Point, usize)> {
+        let (point, distance) = self.queue.pop_front()?;
+        for next in point.neighbours() {
+            if !self.parents.contains_key(&next) && (self.open)(next) {
+                self.parents.insert(next, point);
+                self.queue.push_back((next, distance + 1));
+            }
+        }
+        Some((point, distance))
+    }
+}
+
+/// Draws the grid from (0, 0) to (`width` - 1, `height` - 1) with one
+/// character per point, one row per line.
+pub fn render<F: Fn(Point) -> char>(width: i32, height: i32, cell: F) -> String {
+    let mut txt = String::new();
+    for y in 0..height {
+        txt.extend((0..width).map(|x| cell(Point::new(x, y))));
+        txt.push('\n');
+    }
+    txt
+}
+
+/// Draws `width` x `height` bits, `bit(x, y)` gives a single one, with
+/// `on` and `off` characters.
+pub fn render_bits<F: Fn(usize, usize) -> bool>(width: usize, height: usize, on: char, off: char, bit: F) -> String {
+    render(width as i32, height as i32, |p| if bit(p.x as usize, p.y as usize) { on } else { off })
+}
@@ -0,0 +1,102 @@
+//! MD5 hashes of a salt or passcode followed by something that changes,
+//! as searched by days 5, 14 and 17.
+
+use std::thread;
+
+use md5::{Md5, Digest, digest::FixedOutput};
+
+/// How many hashes are added to a cache at a time.
+const BLOCK: usize = 4096;
+
+/// The MD5 digest of `input`.
+pub fn md5(input: &[u8]) -> [u8; 16] {
+    let mut hash = <Md5 as Digest>::new();
+    hash.update(input);
+    hash.finalize_fixed().into()
+}
+
+/// The digest as lowercase hex digits.
+pub fn to_hex(digest: &[u8; 16]) -> [u8; 32] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = [0; 32];
+    for (i, b) in digest.iter().enumerate() {
+        hex[2 * i] = DIGITS[(b >> 4) as usize];
+        hex[2 * i + 1] = DIGITS[(b & 0xf) as usize];
+    }
+    hex
+}
+
+/// A hasher that has already been fed a common prefix, so only the
+/// suffix is hashed for every input.
+#[derive(Clone)]
+pub struct Seeded {
+    state: Md5,
+}
+
+impl Seeded {
+    pub fn new(prefix: &[u8]) -> Seeded {
+        let mut state = <Md5 as Digest>::new();
+        state.update(prefix);
+        Seeded { state }
+    }
+
+    /// The MD5 digest of the prefix followed by `suffix`.
+    pub fn hash(&self, suffix: &[u8]) -> [u8; 16] {
+        let mut hash = self.state.clone();
+        hash.update(suffix);
+        hash.finalize_fixed().into()
+    }
+}
+
+/// The hex digits of the hash of `input`, hashed again `rounds` times.
+pub fn stretched(input: &[u8], rounds: usize) -> [u8; 32] {
+    let mut hex = to_hex(&md5(input));
+    for _ in 0..rounds {
+        hex = to_hex(&md5(&hex));
+    }
+    hex
+}
+
+/// The stretched hex hashes of a salt followed by each index, computed in
+/// blocks spread over all cores and kept, so every index is hashed once.
+pub struct HashCache {
+    salt: String,
+    rounds: usize,
+    threads: usize,
+    hashes: Vec<[u8; 32]>,
+}
+
+impl HashCache {
+    pub fn new(salt: &str, rounds: usize) -> HashCache {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        HashCache { salt: salt.to_string(), rounds, threads, hashes: Vec::new() }
+    }
+
+    /// The hash of index `index`, computing the missing ones before it.
+    pub fn get(&mut self, index: usize) -> [u8; 32] {
+        if index >= self.hashes.len() {
+            self.extend(index + 1);
+        }
+        self.hashes[index]
+    }
+
+    fn extend(&mut self, end: usize) {
+        let start = self.hashes.len();
+        let end = end.max(start + BLOCK);
+        let chunk = (end - start).div_ceil(self.threads);
+        let (salt, rounds) = (&self.salt, self.rounds);
+        let blocks: Vec<Vec<[u8; 32]>> = thread::scope(|s| {
+            let workers: Vec<_> = (start..end).step_by(chunk)
+                .map(|from| s.spawn(move || {
+                    (from..end.min(from + chunk))
+                        .map(|i| stretched(format!("{}{}", salt, i).as_bytes(), rounds))
+                        .collect()
+                }))
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        for block in blocks {
+            self.hashes.extend(block);
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Solving the Advent of Code 2016
+//! 
+//! Code shared by the solutions of several days.
+
+pub mod assembunny;
+pub mod grid;
+pub mod hashing;
+pub mod math;
@@ -0,0 +1,126 @@
+//! Number theory for the puzzles about things turning in circles, as used
+//! by day 15.
+
+use std::fmt;
+
+/// Why a system of congruences couldn't be solved, with the index of the
+/// congruence where it failed.
+#[derive(Debug, PartialEq)]
+pub enum CrtError {
+    /// a modulus of 0
+    ZeroModulus(usize),
+    /// contradicts the congruences before, which happens when moduli
+    /// share a factor
+    NoSolution(usize),
+    /// the combined modulus or the solution doesn't fit into an `u128`
+    Overflow(usize),
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrtError::ZeroModulus(i) => write!(f, "congruence {} has modulus 0", i + 1),
+            CrtError::NoSolution(i) => write!(f, "no solution, congruence {} contradicts the ones before", i + 1),
+            CrtError::Overflow(i) => write!(f, "combined modulus overflows at congruence {}", i + 1),
+        }
+    }
+}
+
+impl std::error::Error for CrtError {}
+
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The inverse of `a` modulo `m`, if `a` and `m` are coprime. The
+/// coefficients never exceed `m`, so they fit into an `i128`.
+pub fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    // extended Euclid, keeping only the coefficients of a
+    let (mut r0, mut r1) = ((a % m) as i128, m as i128);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(s0.rem_euclid(m as i128) as u64)
+}
+
+/// Solves `x = r (mod m)` for all pairs `(r, m)` of `congruences`, the
+/// moduli don't need to be coprime. Returns the smallest solution and the
+/// modulus all solutions repeat with.
+pub fn crt(congruences: &[(u64, u64)]) -> Result<(u128, u128), CrtError> {
+    let (mut x, mut m) = (0u128, 1u128);
+    for (i, &(r, n)) in congruences.iter().enumerate() {
+        if n == 0 {
+            return Err(CrtError::ZeroModulus(i));
+        }
+        let (r, n) = (r as u128 % n as u128, n as u128);
+        let g = gcd(m, n);
+        // x + m * k = r (mod n)
+        let diff = (r + n - x % n) % n;
+        if diff % g != 0 {
+            return Err(CrtError::NoSolution(i));
+        }
+        let step = n / g;
+        // m / g and n / g are coprime
+        // step divides n, so both fit into an u64
+        let inverse = mod_inverse((m / g % step) as u64, step as u64).unwrap() as u128;
+        let k = (diff / g) % step * inverse % step;
+        let lcm = m.checked_mul(step).ok_or(CrtError::Overflow(i))?;
+        x = x.checked_add(m * k).ok_or(CrtError::Overflow(i))? % lcm;
+        m = lcm;
+    }
+    Ok((x, m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(10, 7), Some(5));
+        assert_eq!(mod_inverse(2, 4), None);
+        assert_eq!(mod_inverse(5, 1), Some(0));
+        assert_eq!(mod_inverse(5, 0), None);
+        assert_eq!(mod_inverse(2, u64::MAX), Some(1 << 63));
+        assert_eq!(mod_inverse(u64::MAX - 1, u64::MAX), Some(u64::MAX - 1));
+        assert_eq!(mod_inverse(3, u64::MAX), None);
+    }
+
+    #[test]
+    fn congruences() {
+        assert_eq!(crt(&[]), Ok((0, 1)));
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        // moduli sharing a factor
+        assert_eq!(crt(&[(1, 4), (3, 6)]), Ok((9, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), Err(CrtError::NoSolution(1)));
+        assert_eq!(crt(&[(1, 4), (2, 0)]), Err(CrtError::ZeroModulus(1)));
+        let big = [(1, u64::MAX), (0, u64::MAX - 1), (2, u64::MAX - 2)];
+        assert_eq!(crt(&big), Err(CrtError::Overflow(2)));
+    }
+
+    #[test]
+    fn brute_force() {
+        for a in 1..12u64 {
+            for b in 1..12u64 {
+                for (ra, rb) in [(0, 0), (1, 2), (a - 1, b / 2)] {
+                    let found = (0..a as u128 * b as u128).find(|x| x % a as u128 == ra as u128 % a as u128
+                                                                 && x % b as u128 == rb as u128 % b as u128);
+                    assert_eq!(crt(&[(ra, a), (rb, b)]).ok().map(|(x, _)| x), found, "{} mod {}, {} mod {}", ra, a, rb, b);
+                }
+            }
+        }
+    }
+}